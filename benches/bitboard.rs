@@ -0,0 +1,61 @@
+//! Compares move generation and occupancy queries on the drawable `Board` with the same work
+//! on a `BitBoard`, and measures make/unmake throughput. Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use pawn_fight::ai::{perft, BitBoard, BitMove};
+use pawn_fight::game_objects::Position;
+
+const ITERATIONS: u32 = 20_000;
+
+fn time<F: FnMut() -> u64>(label: &str, mut f: F) -> Duration {
+    let start = Instant::now();
+    let mut checksum = 0;
+    for _ in 0..ITERATIONS {
+        checksum += f();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<36} {:>10.1} ns/iter  (checksum {})",
+        label,
+        elapsed.as_nanos() as f64 / ITERATIONS as f64,
+        checksum
+    );
+    elapsed
+}
+
+fn main() {
+    let board = Position::all_ready(2).board;
+    let mut bits = BitBoard::from_board(&board);
+    let mut moves: Vec<BitMove> = Vec::with_capacity(64);
+
+    let slow = time("Board legal moves (both players)", || {
+        (board.legal_moves(0).len() + board.legal_moves(1).len()) as u64
+    });
+    let fast = time("BitBoard legal moves (both players)", || {
+        moves.clear();
+        bits.legal_moves(0, &mut moves);
+        bits.legal_moves(1, &mut moves);
+        moves.len() as u64
+    });
+    println!("  speedup {:.1}x", slow.as_secs_f64() / fast.as_secs_f64());
+
+    let slow = time("Board players_remaining", || {
+        board.players_remaining() as u64
+    });
+    let fast = time("BitBoard players_remaining", || {
+        bits.players_remaining() as u64
+    });
+    println!("  speedup {:.1}x", slow.as_secs_f64() / fast.as_secs_f64());
+
+    time("BitBoard make/unmake every move", || {
+        moves.clear();
+        bits.legal_moves(0, &mut moves);
+        for mv in moves.iter() {
+            let undo = bits.make_move(0, *mv);
+            bits.unmake_move(undo);
+        }
+        moves.len() as u64
+    });
+    time("BitBoard perft depth 3", || perft(&mut bits, 0, 3));
+}
@@ -0,0 +1,95 @@
+//! A minimal engine for the bot protocol (see `pawn_fight::protocol`): every time one of its
+//! tokens is Ready it captures if it can and otherwise steps somewhere at random, following
+//! each token's class.
+//!
+//! cargo build --example random_bot && cargo run -- --bot 2 target/debug/examples/random_bot
+
+use std::io::{self, BufRead, Write};
+
+use rand::seq::SliceRandom;
+
+use pawn_fight::game_objects::TokenClass;
+
+struct Seen {
+    owner: i32,
+    pos: (i32, i32),
+    ready: bool,
+    class: TokenClass,
+    promoted: bool,
+}
+
+fn main() {
+    let mut rng = rand::thread_rng();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let (mut cols, mut rows, mut me) = (10, 10, 1);
+    let mut tokens: Vec<Seen> = Vec::new();
+
+    writeln!(out, "name random_bot").unwrap();
+    out.flush().unwrap();
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let num = |i: usize| {
+            words
+                .get(i)
+                .and_then(|w| w.parse::<i32>().ok())
+                .unwrap_or(0)
+        };
+        match words.first() {
+            Some(&"newgame") => {
+                cols = num(1);
+                rows = num(2);
+                me = num(4);
+            }
+            Some(&"position") => tokens.clear(),
+            Some(&"token") => tokens.push(Seen {
+                owner: num(2),
+                pos: (num(3), num(4)),
+                ready: words.get(5) == Some(&"ready"),
+                class: TokenClass::ALL
+                    .iter()
+                    .find(|c| words.get(7) == Some(&c.name()))
+                    .copied()
+                    .unwrap_or_default(),
+                promoted: words.get(8) == Some(&"promoted"),
+            }),
+            Some(&"end") => {
+                let occupant = |pos: (i32, i32)| tokens.iter().find(|t| t.pos == pos);
+                let in_bounds = |p: (i32, i32)| p.0 >= 0 && p.0 < cols && p.1 >= 0 && p.1 < rows;
+                let mut captures = Vec::new();
+                let mut steps = Vec::new();
+                for t in tokens.iter().filter(|t| t.owner == me && t.ready) {
+                    let reach = if t.promoted {
+                        t.class.promoted_captures()
+                    } else {
+                        t.class.captures()
+                    };
+                    for d in reach {
+                        let to = (t.pos.0 + d.0, t.pos.1 + d.1);
+                        if matches!(occupant(to), Some(o) if o.owner != me) {
+                            captures.push((t.pos, to));
+                        }
+                    }
+                    // a runner's long step may be blocked; the game drops such moves
+                    for d in t.class.steps() {
+                        let to = (t.pos.0 + d.0, t.pos.1 + d.1);
+                        if in_bounds(to) && occupant(to).is_none() {
+                            steps.push((t.pos, to));
+                        }
+                    }
+                }
+                let pick = captures.choose(&mut rng).or_else(|| steps.choose(&mut rng));
+                if let Some((from, to)) = pick {
+                    writeln!(out, "move {} {} {} {}", from.0, from.1, to.0, to.1).unwrap();
+                    out.flush().unwrap();
+                }
+            }
+            Some(&"quit") => break,
+            _ => {}
+        }
+    }
+}
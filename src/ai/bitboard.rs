@@ -0,0 +1,372 @@
+use crate::game_objects::{Board, Cell, TokenClass, TokenStates};
+
+/// Largest board, in cells, that fits the 128-bit masks.
+pub const MAX_CELLS: i32 = 128;
+
+/// A move between two cell indices (`row * cols + col`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitMove {
+    pub from: u8,
+    pub to: u8,
+}
+
+/// Everything `unmake_move` needs to restore the position a move was made from.
+#[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    mv: BitMove,
+    player: usize,
+    captured: Option<usize>,
+    captured_ready: bool,
+    captured_class: usize,
+    captured_promoted: bool,
+}
+
+/// Compact board for search: one occupancy mask per player plus a mask of tokens that are
+/// Ready, one mask per token class, a mask of promoted tokens, and masks of the wall and safe
+/// cells from the board's terrain. Token timers are not modelled, only whether a token may
+/// move right now, and neither is promotion: tokens keep whatever promotion they had. Build one
+/// from the drawable `Board` with `from_board`, or bring an existing one back in line with
+/// `sync`, whenever a search starts from the live position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitBoard {
+    cols: i32,
+    rows: i32,
+    players: Vec<u128>,
+    ready: u128,
+    walls: u128,
+    safe: u128,
+    /// Indexed like `TokenClass::ALL`.
+    classes: Vec<u128>,
+    promoted: u128,
+    orth: Vec<u128>,
+    diag: Vec<u128>,
+    knight: Vec<u128>,
+    /// A runner's two-cell steps from each square, as (cell passed over, destination).
+    runs: Vec<Vec<(u8, u8)>>,
+}
+
+fn bit(sq: u8) -> u128 {
+    1u128 << sq
+}
+
+impl BitBoard {
+    /// True if a `cols` x `rows` board fits the masks; `new` panics on any that does not.
+    pub fn fits(cols: i32, rows: i32) -> bool {
+        cols * rows <= MAX_CELLS
+    }
+
+    pub fn new(cols: i32, rows: i32, num_players: i32) -> BitBoard {
+        assert!(
+            BitBoard::fits(cols, rows),
+            "a {}x{} board does not fit a bitboard",
+            cols,
+            rows
+        );
+        let mut orth = Vec::with_capacity((cols * rows) as usize);
+        let mut diag = Vec::with_capacity((cols * rows) as usize);
+        let mut knight = Vec::with_capacity((cols * rows) as usize);
+        let mut runs = Vec::with_capacity((cols * rows) as usize);
+        let inside = |n: (i32, i32)| n.0 >= 0 && n.0 < cols && n.1 >= 0 && n.1 < rows;
+        let sq = |n: (i32, i32)| (n.1 * cols + n.0) as u8;
+        for row in 0..rows {
+            for col in 0..cols {
+                let mask = |offsets: &[(i32, i32)]| {
+                    offsets
+                        .iter()
+                        .map(|d| (col + d.0, row + d.1))
+                        .filter(|n| inside(*n))
+                        .fold(0u128, |m, n| m | bit(sq(n)))
+                };
+                orth.push(mask(&[(1, 0), (-1, 0), (0, 1), (0, -1)]));
+                diag.push(mask(&[(1, 1), (-1, 1), (1, -1), (-1, -1)]));
+                knight.push(mask(TokenClass::Jumper.captures()));
+                runs.push(
+                    [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                        .iter()
+                        .filter(|d| inside((col + 2 * d.0, row + 2 * d.1)))
+                        .map(|d| {
+                            (
+                                sq((col + d.0, row + d.1)),
+                                sq((col + 2 * d.0, row + 2 * d.1)),
+                            )
+                        })
+                        .collect(),
+                );
+            }
+        }
+        BitBoard {
+            cols,
+            rows,
+            players: vec![0; num_players as usize],
+            ready: 0,
+            walls: 0,
+            safe: 0,
+            classes: vec![0; TokenClass::ALL.len()],
+            promoted: 0,
+            orth,
+            diag,
+            knight,
+            runs,
+        }
+    }
+
+    pub fn from_board(board: &Board) -> BitBoard {
+        let mut out = BitBoard::new(board.cols, board.rows, board.num_players());
+        out.sync(board);
+        out
+    }
+
+    /// Overwrites this position with the drawable board's.
+    pub fn sync(&mut self, board: &Board) {
+        for mask in self.players.iter_mut() {
+            *mask = 0;
+        }
+        self.ready = 0;
+        self.walls = 0;
+        self.safe = 0;
+        for mask in self.classes.iter_mut() {
+            *mask = 0;
+        }
+        self.promoted = 0;
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let sq = self.square((col, row));
+                match board.cell((col, row)) {
+                    Cell::Wall => self.walls |= bit(sq),
+                    Cell::Safe => self.safe |= bit(sq),
+                    Cell::Floor | Cell::Haste => {}
+                }
+            }
+        }
+        for (pos, t) in board.tokens() {
+            if t.owner() < 0 || t.owner() as usize >= self.players.len() {
+                continue;
+            }
+            let sq = self.square(pos);
+            self.players[t.owner() as usize] |= bit(sq);
+            self.classes[t.class() as usize] |= bit(sq);
+            if t.state == TokenStates::Ready {
+                self.ready |= bit(sq);
+            }
+            if t.promoted() {
+                self.promoted |= bit(sq);
+            }
+        }
+    }
+
+    pub fn square(&self, pos: (i32, i32)) -> u8 {
+        (pos.1 * self.cols + pos.0) as u8
+    }
+
+    pub fn pos(&self, sq: u8) -> (i32, i32) {
+        (sq as i32 % self.cols, sq as i32 / self.cols)
+    }
+
+    pub fn cells(&self) -> i32 {
+        self.cols * self.rows
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn occupied(&self) -> u128 {
+        self.players.iter().fold(0, |m, p| m | p)
+    }
+
+    pub fn player_mask(&self, p: usize) -> u128 {
+        self.players[p]
+    }
+
+    pub fn ready_mask(&self) -> u128 {
+        self.ready
+    }
+
+    pub fn set_ready(&mut self, sq: u8, ready: bool) {
+        if ready {
+            self.ready |= bit(sq);
+        } else {
+            self.ready &= !bit(sq);
+        }
+    }
+
+    fn owner_of(&self, sq: u8) -> Option<usize> {
+        self.players.iter().position(|m| m & bit(sq) != 0)
+    }
+
+    fn class_of(&self, sq: u8) -> usize {
+        self.classes
+            .iter()
+            .position(|m| m & bit(sq) != 0)
+            .unwrap_or(0)
+    }
+
+    fn targets(&self, p: usize, sq: u8) -> u128 {
+        let i = sq as usize;
+        let enemies = self.occupied() & !self.players[p] & !self.safe;
+        let empty = !self.occupied() & !self.walls;
+        let moves = match TokenClass::ALL[self.class_of(sq)] {
+            TokenClass::Pawn => (self.orth[i] & empty) | (self.diag[i] & enemies),
+            TokenClass::Runner => {
+                let runs = self.runs[i]
+                    .iter()
+                    .filter(|(via, _to)| empty & bit(*via) != 0)
+                    .fold(0, |m, (_via, to)| m | bit(*to));
+                ((self.orth[i] | runs) & empty) | (self.diag[i] & enemies)
+            }
+            TokenClass::Jumper => self.knight[i] & (empty | enemies),
+            TokenClass::Guard => ((self.orth[i] | self.diag[i]) & empty) | (self.orth[i] & enemies),
+        };
+        // promotion adds orthogonal captures to whatever the class captures
+        if self.promoted & bit(sq) != 0 {
+            moves | (self.orth[i] & enemies)
+        } else {
+            moves
+        }
+    }
+
+    /// Same rule as `ClassicRules::is_legal`: a Ready token moves the way its class does,
+    /// stepping onto an empty cell that is not a wall or capturing an enemy not on a safe
+    /// cell. Boards playing a variant are searched as if they played the classic rules.
+    pub fn is_legal(&self, p: usize, mv: BitMove) -> bool {
+        let from = bit(mv.from);
+        self.players[p] & self.ready & from != 0 && self.targets(p, mv.from) & bit(mv.to) != 0
+    }
+
+    /// Appends every legal move for player `p` to `out`.
+    pub fn legal_moves(&self, p: usize, out: &mut Vec<BitMove>) {
+        let mut movers = self.players[p] & self.ready;
+        while movers != 0 {
+            let from = movers.trailing_zeros() as u8;
+            movers &= movers - 1;
+            let mut targets = self.targets(p, from);
+            while targets != 0 {
+                let to = targets.trailing_zeros() as u8;
+                targets &= targets - 1;
+                out.push(BitMove { from, to });
+            }
+        }
+    }
+
+    /// Plays a move assumed legal. As on the real board the mover drops back to waiting.
+    pub fn make_move(&mut self, p: usize, mv: BitMove) -> Undo {
+        let to = bit(mv.to);
+        let captured = self.owner_of(mv.to);
+        let captured_ready = self.ready & to != 0;
+        let captured_class = self.class_of(mv.to);
+        let captured_promoted = self.promoted & to != 0;
+        if let Some(victim) = captured {
+            self.players[victim] &= !to;
+            self.classes[captured_class] &= !to;
+        }
+        let moved_promoted = self.promoted & bit(mv.from) != 0;
+        self.promoted &= !(bit(mv.from) | to);
+        if moved_promoted {
+            self.promoted |= to;
+        }
+        let class = self.class_of(mv.from);
+        self.classes[class] = (self.classes[class] & !bit(mv.from)) | to;
+        self.players[p] = (self.players[p] & !bit(mv.from)) | to;
+        self.ready &= !(bit(mv.from) | to);
+        Undo {
+            mv,
+            player: p,
+            captured,
+            captured_ready,
+            captured_class,
+            captured_promoted,
+        }
+    }
+
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let (from, to) = (bit(undo.mv.from), bit(undo.mv.to));
+        self.players[undo.player] = (self.players[undo.player] & !to) | from;
+        self.ready |= from;
+        let class = self.class_of(undo.mv.to);
+        self.classes[class] = (self.classes[class] & !to) | from;
+        if self.promoted & to != 0 {
+            self.promoted = (self.promoted & !to) | from;
+        }
+        if let Some(victim) = undo.captured {
+            self.players[victim] |= to;
+            self.classes[undo.captured_class] |= to;
+            if undo.captured_promoted {
+                self.promoted |= to;
+            }
+            if undo.captured_ready {
+                self.ready |= to;
+            }
+        }
+    }
+
+    pub fn tokens_remaining(&self, p: usize) -> u32 {
+        self.players[p].count_ones()
+    }
+
+    pub fn players_remaining(&self) -> i32 {
+        self.players.iter().filter(|m| **m != 0).count() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    /// Every class, a promoted token on each side and captures available to both players.
+    const MIXED: &str = "\
+0r 0rJ . 1r .
+0rR . 1rG . .
+. 0rG+ . 1rJ 1r
+. . . . .
+0rR . . . 1rR+
+";
+
+    fn mixed() -> Board {
+        Position::parse(MIXED).unwrap().board
+    }
+
+    fn cells(bits: &BitBoard, moves: &[BitMove]) -> Vec<((i32, i32), (i32, i32))> {
+        let mut out: Vec<_> = moves
+            .iter()
+            .map(|mv| (bits.pos(mv.from), bits.pos(mv.to)))
+            .collect();
+        out.sort();
+        out
+    }
+
+    fn round_trip(bits: &mut BitBoard, p: usize, depth: u32) {
+        if depth == 0 {
+            return;
+        }
+        let mut moves = Vec::new();
+        bits.legal_moves(p, &mut moves);
+        for mv in moves {
+            let before = bits.clone();
+            let undo = bits.make_move(p, mv);
+            round_trip(bits, (p + 1) % bits.num_players(), depth - 1);
+            bits.unmake_move(undo);
+            assert_eq!(*bits, before, "unmaking {:?} for player {}", mv, p);
+        }
+    }
+
+    #[test]
+    fn unmake_restores_the_position() {
+        let mut bits = BitBoard::from_board(&mixed());
+        round_trip(&mut bits, 0, 3);
+        round_trip(&mut bits, 1, 3);
+    }
+
+    #[test]
+    fn legal_moves_match_the_board() {
+        let board = mixed();
+        let bits = BitBoard::from_board(&board);
+        for p in 0..2 {
+            let mut moves = Vec::new();
+            bits.legal_moves(p, &mut moves);
+            let mut expected = board.legal_moves(p as i32);
+            expected.sort();
+            assert_eq!(cells(&bits, &moves), expected, "player {}", p);
+        }
+    }
+}
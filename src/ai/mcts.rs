@@ -0,0 +1,357 @@
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::game_objects::{Board, Controller, TokenStates};
+
+/// Seconds per board update inside a simulated step, fine enough that timers expire close
+/// to when they would in the real game.
+const SIM_TICK: f64 = 0.1;
+/// Chance a rollout player steps somewhere rather than holding when it cannot capture.
+const ROLLOUT_STEP_CHANCE: f64 = 0.3;
+/// Weight of the material balance in a position's score.
+const MATERIAL_WEIGHT: f64 = 0.85;
+/// Weight of the pull towards the enemy in a quiet position's score.
+const CLOSENESS_WEIGHT: f64 = 0.1;
+
+#[derive(Clone, Copy, Debug)]
+pub struct MctsConfig {
+    /// Wall-clock time allowed per decision; `None` stops on `max_iterations` alone, so a
+    /// seeded player always makes the same decisions.
+    pub budget: Option<Duration>,
+    /// Upper bound on iterations per decision. With a budget set, the clock usually stops the
+    /// search first, and how far it got depends on the machine.
+    pub max_iterations: u32,
+    /// Simulated seconds between decisions inside the tree and rollouts.
+    pub step: f64,
+    /// How many of our own decisions the tree looks ahead before handing over to rollouts.
+    pub tree_depth: u32,
+    /// Simulated seconds from now at which a rollout stops and the position is scored.
+    pub horizon: f64,
+    /// UCB1 exploration constant.
+    pub exploration: f64,
+    /// Game seconds between decisions while any of our tokens is Ready.
+    pub decide_every: f64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            budget: Some(Duration::from_millis(20)),
+            max_iterations: 2000,
+            step: 0.5,
+            tree_depth: 4,
+            horizon: 8.0,
+            exploration: 1.4,
+            decide_every: 0.25,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Action {
+    Hold,
+    Move((i32, i32), (i32, i32)),
+}
+
+struct Node {
+    visits: u32,
+    value: f64,
+    children: Vec<(Action, usize)>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            visits: 0,
+            value: 0.0,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Score in -1..1 from `p`'s point of view: a win or loss outright, otherwise the material
+/// balance against the strongest opponent, with a small pull towards the enemy so quiet
+/// positions still favour closing in over drifting.
+fn evaluate(board: &Board, p: i32) -> f64 {
+    match board.winner() {
+        Some(w) if w == p => return 1.0,
+        Some(_) => return -1.0,
+        None => {}
+    }
+    let mine = board.tokens_remaining(p);
+    if mine == 0 {
+        return -1.0;
+    }
+    let theirs = (0..board.num_players())
+        .filter(|q| *q != p)
+        .map(|q| board.tokens_remaining(q))
+        .max()
+        .unwrap_or(0);
+    if theirs == 0 {
+        return 1.0;
+    }
+    let tokens = board.tokens();
+    let enemies: Vec<(i32, i32)> = tokens
+        .iter()
+        .filter(|(_pos, t)| t.owner() != p)
+        .map(|(pos, _t)| *pos)
+        .collect();
+    let span = (board.cols + board.rows) as f64;
+    let closeness: f64 = tokens
+        .iter()
+        .filter(|(_pos, t)| t.owner() == p)
+        .map(|(pos, _t)| {
+            let nearest = enemies.iter().map(|e| Board::manhattan(*pos, *e)).min();
+            1.0 - nearest.unwrap_or(0) as f64 / span
+        })
+        .sum::<f64>()
+        / mine as f64;
+    let total = (mine + theirs) as f64;
+    MATERIAL_WEIGHT * (mine - theirs) as f64 / total + CLOSENESS_WEIGHT * closeness
+}
+
+/// Open-loop Monte Carlo tree search over the real `Board`, so token timers run exactly as
+/// in play: copies of the board are stepped forward with everyone's Wait, Prep and Ready
+/// cycles ticking, the tree covers our own choices (any legal move, or holding still) at
+/// each decision point, and opponents and rollouts play a capture-first random policy.
+/// Whether a token is worth moving now therefore depends on when it and the enemy tokens
+/// around it will next be Ready.
+pub struct MctsPlayer {
+    player_num: i32,
+    config: MctsConfig,
+    rng: StdRng,
+    last_decision: Option<f64>,
+    next: Option<((i32, i32), (i32, i32))>,
+}
+
+impl MctsPlayer {
+    pub fn new(p: i32, config: MctsConfig, seed: u64) -> MctsPlayer {
+        MctsPlayer {
+            player_num: p,
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            last_decision: None,
+            next: None,
+        }
+    }
+
+    /// Picks a move for everyone but `me` with the rollout policy and plays all of them,
+    /// along with `mine`, in a random order, then runs the clock for one step.
+    fn advance(&mut self, sim: &mut Board, me: i32, mine: Action) {
+        let mut order: Vec<i32> = (0..sim.num_players()).collect();
+        order.shuffle(&mut self.rng);
+        for p in order {
+            let action = if p == me {
+                mine
+            } else {
+                self.rollout_action(sim, p)
+            };
+            if let Action::Move(from, to) = action {
+                sim.check_and_move_token(p, from, to);
+            }
+        }
+        let mut elapsed = 0.0;
+        while elapsed < self.config.step {
+            sim.update(SIM_TICK);
+            elapsed += SIM_TICK;
+        }
+        sim.drain_events();
+    }
+
+    /// Any capture `p` has, picked at random, otherwise a random step now and then.
+    fn rollout_action(&mut self, sim: &Board, p: i32) -> Action {
+        let moves = sim.legal_moves(p);
+        // legal_moves lists the captures first
+        let captures = moves
+            .iter()
+            .take_while(|(_from, to)| sim.token_at(*to).is_some())
+            .count();
+        if captures > 0 {
+            let (from, to) = moves[self.rng.gen_range(0..captures)];
+            Action::Move(from, to)
+        } else if !moves.is_empty() && self.rng.gen_bool(ROLLOUT_STEP_CHANCE) {
+            let (from, to) = moves[self.rng.gen_range(0..moves.len())];
+            Action::Move(from, to)
+        } else {
+            Action::Hold
+        }
+    }
+
+    fn iterate(&mut self, root: &Board, nodes: &mut Vec<Node>) {
+        let me = self.player_num;
+        let mut sim = root.clone();
+        let mut path = vec![0];
+        let mut node = 0;
+        let mut depth = 0;
+        let mut simulated = 0.0;
+
+        // selection and expansion
+        while depth < self.config.tree_depth && sim.winner().is_none() {
+            let mut actions = vec![Action::Hold];
+            actions.extend(
+                sim.legal_moves(me)
+                    .into_iter()
+                    .map(|(f, t)| Action::Move(f, t)),
+            );
+            let untried: Vec<Action> = actions
+                .iter()
+                .filter(|a| !nodes[node].children.iter().any(|(c, _)| c == *a))
+                .copied()
+                .collect();
+            let (action, child, expanded) = if let Some(action) = untried.choose(&mut self.rng) {
+                nodes.push(Node::new());
+                let child = nodes.len() - 1;
+                nodes[node].children.push((*action, child));
+                (*action, child, true)
+            } else {
+                let parent_visits = nodes[node].visits.max(1) as f64;
+                let c = self.config.exploration;
+                let mut best: Option<(f64, Action, usize)> = None;
+                for (a, idx) in nodes[node].children.iter() {
+                    if !actions.contains(a) {
+                        continue;
+                    }
+                    let n = &nodes[*idx];
+                    let visits = n.visits.max(1) as f64;
+                    let ucb = n.value / visits + c * (parent_visits.ln() / visits).sqrt();
+                    if !matches!(best, Some((b, _, _)) if b >= ucb) {
+                        best = Some((ucb, *a, *idx));
+                    }
+                }
+                match best {
+                    Some((_ucb, a, idx)) => (a, idx, false),
+                    None => break,
+                }
+            };
+            self.advance(&mut sim, me, action);
+            simulated += self.config.step;
+            path.push(child);
+            node = child;
+            depth += 1;
+            if expanded {
+                break;
+            }
+        }
+
+        // rollout
+        while simulated < self.config.horizon && sim.winner().is_none() {
+            let mine = self.rollout_action(&sim, me);
+            self.advance(&mut sim, me, mine);
+            simulated += self.config.step;
+        }
+
+        let score = evaluate(&sim, me);
+        for idx in path {
+            nodes[idx].visits += 1;
+            nodes[idx].value += score;
+        }
+    }
+
+    /// Searches from `board` within the time and iteration budget and returns the most
+    /// visited first move, or `None` if holding still looks best.
+    pub fn search(&mut self, board: &Board) -> Option<((i32, i32), (i32, i32))> {
+        let started = Instant::now();
+        let mut nodes = vec![Node::new()];
+        let mut iterations = 0;
+        while iterations < self.config.max_iterations
+            && !matches!(self.config.budget, Some(budget) if started.elapsed() >= budget)
+        {
+            self.iterate(board, &mut nodes);
+            iterations += 1;
+        }
+        let best = nodes[0]
+            .children
+            .iter()
+            .max_by_key(|(_a, idx)| nodes[*idx].visits)
+            .map(|(a, _idx)| *a);
+        match best {
+            Some(Action::Move(from, to)) => Some((from, to)),
+            _ => None,
+        }
+    }
+}
+
+impl Controller for MctsPlayer {
+    fn player_num(&self) -> i32 {
+        self.player_num
+    }
+
+    fn name(&self) -> String {
+        match self.config.budget {
+            Some(budget) => format!(
+                "Player {} (mcts {}ms)",
+                self.player_num + 1,
+                budget.as_millis()
+            ),
+            None => format!(
+                "Player {} (mcts {} iterations)",
+                self.player_num + 1,
+                self.config.max_iterations
+            ),
+        }
+    }
+
+    fn observe(&mut self, board: &Board, time: f64) {
+        // nothing to decide until one of our tokens can move
+        let any_ready = board
+            .tokens()
+            .iter()
+            .any(|(_pos, t)| t.owner() == self.player_num && t.state == TokenStates::Ready);
+        if !any_ready {
+            return;
+        }
+        if matches!(self.last_decision, Some(last) if time - last < self.config.decide_every) {
+            return;
+        }
+        self.last_decision = Some(time);
+        self.next = self.search(board);
+    }
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        self.next.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    #[test]
+    fn iteration_bound_searches_are_reproducible() {
+        let board = Position::all_ready(2).board;
+        let config = MctsConfig {
+            budget: None,
+            max_iterations: 200,
+            ..MctsConfig::default()
+        };
+        let first = MctsPlayer::new(0, config, 5).search(&board);
+        for _ in 0..3 {
+            assert_eq!(MctsPlayer::new(0, config, 5).search(&board), first);
+        }
+    }
+
+    #[test]
+    fn a_capture_is_only_taken_when_the_recapture_is_not_ready() {
+        // taking the waiting pawn at (3, 3) lands on a cell the pawn at (4, 2) captures on
+        let config = MctsConfig {
+            budget: None,
+            max_iterations: 400,
+            ..MctsConfig::default()
+        };
+        let capture = Some(((2, 2), (3, 3)));
+        let grid = |enemy: &str| {
+            format!(
+                ". . . . .\n. . . . .\n. . 0r . {}\n. . . 1w:0/30 .\n. . . . .",
+                enemy
+            )
+        };
+        let waiting = Position::parse(&grid("1w:0/30")).unwrap().board;
+        assert_eq!(MctsPlayer::new(0, config, 5).search(&waiting), capture);
+        let ready = Position::parse(&grid("1r")).unwrap().board;
+        assert_ne!(MctsPlayer::new(0, config, 5).search(&ready), capture);
+    }
+}
@@ -0,0 +1,9 @@
+mod bitboard;
+mod mcts;
+mod players;
+mod search;
+
+pub use self::bitboard::{BitBoard, BitMove, Undo};
+pub use self::mcts::{MctsConfig, MctsPlayer};
+pub use self::players::{RandomPlayer, SearchPlayer};
+pub use self::search::{best_move, perft};
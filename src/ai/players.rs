@@ -0,0 +1,129 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::ai::{best_move, BitBoard, BitMove};
+use crate::game_objects::{Board, Controller};
+
+fn to_cells(bits: &BitBoard, mv: BitMove) -> ((i32, i32), (i32, i32)) {
+    (bits.pos(mv.from), bits.pos(mv.to))
+}
+
+/// Plays a uniformly random legal move whenever it has one; the baseline every other bot
+/// should beat.
+pub struct RandomPlayer {
+    player_num: i32,
+    rng: StdRng,
+    bits: Option<BitBoard>,
+    next: Option<((i32, i32), (i32, i32))>,
+}
+
+impl RandomPlayer {
+    pub fn new(p: i32, seed: u64) -> RandomPlayer {
+        RandomPlayer {
+            player_num: p,
+            rng: StdRng::seed_from_u64(seed),
+            bits: None,
+            next: None,
+        }
+    }
+}
+
+impl Controller for RandomPlayer {
+    fn player_num(&self) -> i32 {
+        self.player_num
+    }
+
+    fn name(&self) -> String {
+        format!("Player {} (random)", self.player_num + 1)
+    }
+
+    fn observe(&mut self, board: &Board, _time: f64) {
+        if !BitBoard::fits(board.cols, board.rows) {
+            self.next = board
+                .legal_moves(self.player_num)
+                .choose(&mut self.rng)
+                .copied();
+            return;
+        }
+        let bits = self.bits.get_or_insert_with(|| BitBoard::from_board(board));
+        bits.sync(board);
+        let mut moves = Vec::new();
+        bits.legal_moves(self.player_num as usize, &mut moves);
+        self.next = moves.choose(&mut self.rng).map(|mv| to_cells(bits, *mv));
+    }
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        self.next.take()
+    }
+}
+
+/// Plays the move `best_move` finds at a fixed depth whenever it has one. Boards too big for
+/// a `BitBoard` get a one-ply greedy move instead.
+pub struct SearchPlayer {
+    player_num: i32,
+    depth: u32,
+    bits: Option<BitBoard>,
+    next: Option<((i32, i32), (i32, i32))>,
+}
+
+impl SearchPlayer {
+    pub fn new(p: i32, depth: u32) -> SearchPlayer {
+        SearchPlayer {
+            player_num: p,
+            depth,
+            bits: None,
+            next: None,
+        }
+    }
+}
+
+impl Controller for SearchPlayer {
+    fn player_num(&self) -> i32 {
+        self.player_num
+    }
+
+    fn name(&self) -> String {
+        format!("Player {} (search {})", self.player_num + 1, self.depth)
+    }
+
+    fn observe(&mut self, board: &Board, _time: f64) {
+        if !BitBoard::fits(board.cols, board.rows) {
+            // Too big to search: take a capture if there is one, otherwise any step.
+            self.next = board.legal_moves(self.player_num).first().copied();
+            return;
+        }
+        let bits = self.bits.get_or_insert_with(|| BitBoard::from_board(board));
+        bits.sync(board);
+        self.next =
+            best_move(bits, self.player_num as usize, self.depth).map(|mv| to_cells(bits, mv));
+    }
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        self.next.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Map, Setup};
+
+    #[test]
+    fn bots_play_on_boards_too_big_for_a_bitboard() {
+        let map = Map::open(12, 12);
+        assert!(!BitBoard::fits(map.cols, map.rows));
+        let mut board = Board::generate_on(&map, 2, Setup::Classic, 0, &[], &[]);
+        while board.legal_moves(0).is_empty() || board.legal_moves(1).is_empty() {
+            board.update(0.1);
+        }
+        let mut random = RandomPlayer::new(0, 3);
+        random.observe(&board, 1.5);
+        let (from, to) = random.take_move().unwrap();
+        assert!(board.is_legal(0, from, to));
+        let mut search = SearchPlayer::new(1, 2);
+        search.observe(&board, 1.5);
+        let (from, to) = search.take_move().unwrap();
+        assert!(board.is_legal(1, from, to));
+    }
+}
@@ -0,0 +1,136 @@
+use crate::ai::bitboard::{BitBoard, BitMove};
+
+const WIN_SCORE: i32 = 10_000;
+
+fn next_player(board: &BitBoard, p: usize) -> usize {
+    (p + 1) % board.num_players()
+}
+
+/// Material from `p`'s point of view: its tokens minus everyone else's.
+fn evaluate(board: &BitBoard, p: usize) -> i32 {
+    let mine = board.tokens_remaining(p) as i32;
+    if mine == 0 {
+        return -WIN_SCORE;
+    }
+    let theirs: i32 = (0..board.num_players())
+        .filter(|q| *q != p)
+        .map(|q| board.tokens_remaining(q) as i32)
+        .sum();
+    if theirs == 0 {
+        return WIN_SCORE;
+    }
+    mine - theirs
+}
+
+fn negamax(board: &mut BitBoard, p: usize, depth: u32, mut alpha: i32, beta: i32) -> i32 {
+    if depth == 0 || board.players_remaining() <= 1 {
+        return evaluate(board, p);
+    }
+    let mut moves = Vec::new();
+    board.legal_moves(p, &mut moves);
+    if moves.is_empty() {
+        // nothing is Ready, so the turn passes
+        return -negamax(board, next_player(board, p), depth - 1, -beta, -alpha);
+    }
+    let mut best = -WIN_SCORE - 1;
+    for mv in moves {
+        let undo = board.make_move(p, mv);
+        let score = -negamax(board, next_player(board, p), depth - 1, -beta, -alpha);
+        board.unmake_move(undo);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+/// Alpha-beta search over alternating turns with only the tokens that are Ready now able to
+/// move. Returns `None` when `p` has nothing to move.
+pub fn best_move(board: &mut BitBoard, p: usize, depth: u32) -> Option<BitMove> {
+    let mut moves = Vec::new();
+    board.legal_moves(p, &mut moves);
+    let mut best: Option<(i32, BitMove)> = None;
+    for mv in moves {
+        let undo = board.make_move(p, mv);
+        let score = -negamax(
+            board,
+            next_player(board, p),
+            depth.saturating_sub(1),
+            -WIN_SCORE - 1,
+            WIN_SCORE + 1,
+        );
+        board.unmake_move(undo);
+        if !matches!(best, Some((s, _)) if s >= score) {
+            best = Some((score, mv));
+        }
+    }
+    best.map(|(_, mv)| mv)
+}
+
+/// Counts the leaves of the move tree to `depth`, the usual check that make/unmake and move
+/// generation agree with each other.
+pub fn perft(board: &mut BitBoard, p: usize, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut moves = Vec::new();
+    board.legal_moves(p, &mut moves);
+    if moves.is_empty() {
+        return perft(board, next_player(board, p), depth - 1);
+    }
+    let mut nodes = 0;
+    for mv in moves {
+        let undo = board.make_move(p, mv);
+        nodes += perft(board, next_player(board, p), depth - 1);
+        board.unmake_move(undo);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Board, Position};
+
+    fn start() -> Board {
+        Position::all_ready(2).board
+    }
+
+    /// The same count played out on the drawable board.
+    fn board_perft(board: &Board, p: i32, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let next = (p + 1) % board.num_players();
+        let moves = board.legal_moves(p);
+        if moves.is_empty() {
+            return board_perft(board, next, depth - 1);
+        }
+        moves
+            .into_iter()
+            .map(|(from, to)| {
+                let mut after = board.clone();
+                after.check_and_move_token(p, from, to);
+                board_perft(&after, next, depth - 1)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn perft_from_the_start_position() {
+        let mut bits = BitBoard::from_board(&start());
+        let counts: Vec<u64> = (1..=4).map(|depth| perft(&mut bits, 0, depth)).collect();
+        assert_eq!(counts, vec![6, 36, 204, 1156]);
+    }
+
+    #[test]
+    fn perft_agrees_with_the_board() {
+        let board = start();
+        let mut bits = BitBoard::from_board(&board);
+        for depth in 1..=3 {
+            assert_eq!(perft(&mut bits, 0, depth), board_perft(&board, 0, depth));
+        }
+    }
+}
@@ -0,0 +1,228 @@
+#![warn(rust_2018_idioms)]
+
+//! Headless bot-vs-bot matches: every pair of bots plays `--games` matches, swapping seats
+//! each game, and the totals are printed as a table.
+//!
+//! ```text
+//! tournament [--games N] [--seed S] [--layout NAME] [--mode MODE] [--handicap SEAT SPEC]
+//!            [--army SEAT CLASSES] [--map FILE] [--time-limit SECS] [--draw-after SECS]
+//!            [--sudden-death SPEC] [--promotion] [--max-time SECS] [--tick SECS] [--realtime]
+//!            BOT BOT...
+//! ```
+//!
+//! where a BOT is `random`, `search:<depth>`, `mcts:<milliseconds per decision>`,
+//! `mcts:i<iterations per decision>` or `exec:<command>` for an engine speaking the bot protocol.
+//! External engines usually want `--realtime`, since they only see positions as fast as the
+//! simulation produces them. Each game's seed drives both the starting layout and the bots' own
+//! randomness, so a run is reproducible from `--seed` as long as every bot is deterministic: a
+//! timed `mcts:<ms>` searches as far as the machine gets, while `mcts:i<N>` always searches the
+//! same tree. A handicap such as `--handicap 2 3x1.5` (3 tokens, 1.5 times the cooldowns) belongs
+//! to a seat, so with seats swapping every game each bot plays both sides of it, as does an army
+//! such as `--army 1 PRJG`. `--mode` picks the victory condition, e.g. `hill:45` or `flag`.
+//! `--time-limit` decides a match still going by surviving tokens then kills, and `--draw-after`
+//! calls it a draw once no capture has been possible for that long; `--sudden-death 120:20` starts
+//! closing in the board's outer rings two minutes in, one ring every 20 seconds; `--promotion`
+//! promotes tokens that reach the far edge; `--max-time` stays a hard cap that ends any match as a
+//! draw.
+
+use std::time::Duration;
+
+use pawn_fight::{
+    ai::{MctsConfig, MctsPlayer, RandomPlayer, SearchPlayer},
+    game::Game,
+    game_objects::{
+        Controller, ExternalPlayer, GameMode, Handicap, Map, MatchOptions, Setup, SuddenDeath,
+        TokenClass,
+    },
+    tournament::{play_match, MatchConfig, Standings},
+};
+
+const DEFAULT_GAMES: u32 = 10;
+/// Players per match: every pairing plays head to head.
+const SEATS: usize = 2;
+
+struct Args {
+    games: u32,
+    options: MatchOptions,
+    config: MatchConfig,
+    bots: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut out = Args {
+        games: DEFAULT_GAMES,
+        options: MatchOptions::default(),
+        config: MatchConfig::default(),
+        bots: Vec::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("{} requires a value", name))
+        };
+        match arg.as_str() {
+            "--games" => out.games = parse_num(&arg, &value(&arg)?)?,
+            "--seed" => out.options.seed = parse_num(&arg, &value(&arg)?)?,
+            "--layout" => out.options.setup = Setup::parse(&value(&arg)?)?,
+            "--mode" => out.options.mode = GameMode::parse(&value(&arg)?)?,
+            "--time-limit" => out.options.time_limit = Some(parse_seconds(&arg, &value(&arg)?)?),
+            "--draw-after" => out.options.draw_after = Some(parse_seconds(&arg, &value(&arg)?)?),
+            "--promotion" => out.options.promotion = true,
+            "--sudden-death" => out.options.sudden_death = Some(SuddenDeath::parse(&value(&arg)?)?),
+            "--map" => out.options.map = Some(Map::load(&value(&arg)?)?),
+            "--handicap" => {
+                let seat: usize = parse_num(&arg, &value(&arg)?)?;
+                let handicap = Handicap::parse(&value(&arg)?)?;
+                if !(1..=SEATS).contains(&seat) {
+                    return Err(format!("--handicap seat must be 1 to {}", SEATS));
+                }
+                let handicaps = &mut out.options.handicaps;
+                if handicaps.len() < seat {
+                    handicaps.resize(seat, Handicap::default());
+                }
+                handicaps[seat - 1] = handicap;
+            }
+            "--army" => {
+                let seat: usize = parse_num(&arg, &value(&arg)?)?;
+                let army = TokenClass::parse_army(&value(&arg)?)?;
+                if !(1..=SEATS).contains(&seat) {
+                    return Err(format!("--army seat must be 1 to {}", SEATS));
+                }
+                let armies = &mut out.options.armies;
+                if armies.len() < seat {
+                    armies.resize(seat, Vec::new());
+                }
+                armies[seat - 1] = army;
+            }
+            "--max-time" => out.config.max_seconds = parse_seconds(&arg, &value(&arg)?)?,
+            "--tick" => out.config.tick = parse_seconds(&arg, &value(&arg)?)?,
+            "--realtime" => out.config.realtime = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ => out.bots.push(arg),
+        }
+    }
+    if out.bots.len() < 2 {
+        return Err(String::from(
+            "usage: tournament [--games N] [--seed S] [--layout NAME] [--mode MODE] [--handicap SEAT SPEC] \
+             [--army SEAT CLASSES] [--map FILE] [--time-limit SECS] [--draw-after SECS] [--sudden-death SPEC] [--promotion] [--max-time SECS] [--tick SECS] [--realtime] BOT BOT...\n  BOT is random, search:<depth>, mcts:<ms>, mcts:i<iterations> or exec:<command>",
+        ));
+    }
+    Ok(out)
+}
+
+fn parse_num<T: std::str::FromStr>(name: &str, text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("{}: cannot read '{}'", name, text))
+}
+
+/// Reads a length of time, which must be a positive number of seconds.
+fn parse_seconds(name: &str, text: &str) -> Result<f64, String> {
+    let seconds: f64 = parse_num(name, text)?;
+    if !seconds.is_finite() || seconds <= 0.0 {
+        return Err(format!(
+            "{}: expected a positive number of seconds, got '{}'",
+            name, text
+        ));
+    }
+    Ok(seconds)
+}
+
+/// Builds the controller a bot spec describes, seated as player `p`.
+fn make_bot(spec: &str, p: i32, seed: u64) -> Result<Box<dyn Controller>, String> {
+    if spec == "random" {
+        return Ok(Box::new(RandomPlayer::new(p, seed)));
+    }
+    if let Some(depth) = spec.strip_prefix("search:") {
+        return Ok(Box::new(SearchPlayer::new(p, parse_num(spec, depth)?)));
+    }
+    if let Some(iterations) = spec.strip_prefix("mcts:i") {
+        let config = MctsConfig {
+            budget: None,
+            max_iterations: parse_num(spec, iterations)?,
+            ..MctsConfig::default()
+        };
+        return Ok(Box::new(MctsPlayer::new(p, config, seed)));
+    }
+    if let Some(ms) = spec.strip_prefix("mcts:") {
+        let config = MctsConfig {
+            budget: Some(Duration::from_millis(parse_num(spec, ms)?)),
+            ..MctsConfig::default()
+        };
+        return Ok(Box::new(MctsPlayer::new(p, config, seed)));
+    }
+    if let Some(command) = spec.strip_prefix("exec:") {
+        return Ok(Box::new(ExternalPlayer::spawn(p, command)?));
+    }
+    Err(format!("unknown bot '{}'", spec))
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let mut standings = Standings::new(args.bots.len());
+    let mut seed = args.options.seed;
+
+    for a in 0..args.bots.len() {
+        for b in a + 1..args.bots.len() {
+            let mut pairing = [0; 3];
+            for game_num in 0..args.games {
+                // swap seats every game so neither bot always gets the same side
+                let seats = if game_num % 2 == 1 { [b, a] } else { [a, b] };
+                let options = MatchOptions {
+                    seed,
+                    ..args.options.clone()
+                };
+                let mut game = Game::with_options(Vec::new(), options);
+                for (p, bot) in seats.iter().enumerate() {
+                    game.add_controller(make_bot(
+                        &args.bots[*bot],
+                        p as i32,
+                        seed.wrapping_add(p as u64),
+                    )?);
+                }
+                seed = seed.wrapping_add(1);
+                let result = play_match(&mut game, &args.config);
+                match standings.record(&seats, &result) {
+                    Some(winner) if winner == a => pairing[0] += 1,
+                    Some(_) => pairing[1] += 1,
+                    None => pairing[2] += 1,
+                }
+            }
+            println!(
+                "{} vs {}: {}-{}-{}",
+                args.bots[a], args.bots[b], pairing[0], pairing[1], pairing[2]
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "{:<24} {:>6} {:>6} {:>6} {:>6} {:>10}",
+        "bot", "games", "wins", "losses", "draws", "kills/game"
+    );
+    for (spec, s) in args.bots.iter().zip(standings.bots.iter()) {
+        println!(
+            "{:<24} {:>6} {:>6} {:>6} {:>6} {:>10.2}",
+            spec,
+            s.games,
+            s.wins,
+            s.losses,
+            s.draws,
+            s.kills_per_game()
+        );
+    }
+    println!();
+    println!(
+        "average match length: {:.1}s over {} games",
+        standings.average_seconds(),
+        standings.games
+    );
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(|args| run(&args));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
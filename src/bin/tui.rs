@@ -0,0 +1,347 @@
+#![warn(rust_2018_idioms)]
+
+//! Terminal frontend: plays the same match as the window, drawn with Unicode glyphs and ANSI
+//! colors so it works over SSH and on headless servers.
+//!
+//! Player 1 moves with WASD and selects with Space, player 2 with the arrow keys and Enter.
+//! `--players 1` seats only player 1, which is handy for poking at the rules, and `--mode`,
+//! `--sudden-death` and `--promotion` pick the victory condition, shrinking arena and
+//! promotion as in the window.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{self, Color},
+    terminal,
+};
+use piston_window::Key;
+
+use pawn_fight::{
+    drawing::color,
+    game::Game,
+    game_objects::{
+        Cell, GameMode, HumanPlayer, InputTypes, MatchOptions, SuddenDeath, TokenClass, TokenStates,
+    },
+};
+
+const TICK: f64 = 1.0 / 60.0;
+/// Ticks between redraws; the countdowns only need a few frames a second.
+const REDRAW_EVERY: u64 = 6;
+const CELL_WIDTH: usize = 4;
+/// How strongly a ring about to collapse is tinted red.
+const COLLAPSE_ALPHA: f32 = 0.5;
+
+/// Puts the terminal back the way we found it, even if the game panics.
+struct RawScreen;
+
+impl RawScreen {
+    fn enter(out: &mut impl Write) -> crossterm::Result<RawScreen> {
+        terminal::enable_raw_mode()?;
+        queue!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        out.flush()?;
+        Ok(RawScreen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = queue!(
+            out,
+            style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn parse_args() -> Result<(i32, MatchOptions), String> {
+    let mut players = 2;
+    let mut options = MatchOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=2) => players = n,
+                _ => return Err(String::from("--players requires 1 or 2")),
+            },
+            "--mode" => match args.next() {
+                Some(spec) => options.mode = GameMode::parse(&spec)?,
+                None => return Err(String::from("--mode requires <name>[:seconds]")),
+            },
+            "--promotion" => options.promotion = true,
+            "--sudden-death" => match args.next() {
+                Some(spec) => options.sudden_death = Some(SuddenDeath::parse(&spec)?),
+                None => {
+                    return Err(String::from(
+                        "--sudden-death requires <start>[:<every>[:<warning>]]",
+                    ))
+                }
+            },
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+    Ok((players, options))
+}
+
+/// `over` laid on `under` by its alpha, since the terminal has no transparency.
+fn blend(under: [f32; 4], over: [f32; 4]) -> [f32; 4] {
+    let a = over[3];
+    [
+        under[0] * (1.0 - a) + over[0] * a,
+        under[1] * (1.0 - a) + over[1] * a,
+        under[2] * (1.0 - a) + over[2] * a,
+        1.0,
+    ]
+}
+
+fn rgb(c: [f32; 4]) -> Color {
+    Color::Rgb {
+        r: (c[0] * 255.0) as u8,
+        g: (c[1] * 255.0) as u8,
+        b: (c[2] * 255.0) as u8,
+    }
+}
+
+/// Maps a terminal key to the player it belongs to and the piston key that player expects.
+fn route_key(key: KeyCode, players: i32) -> Option<(i32, Key)> {
+    let p1 = match key {
+        KeyCode::Char('w') => Some(Key::Up),
+        KeyCode::Char('a') => Some(Key::Left),
+        KeyCode::Char('s') => Some(Key::Down),
+        KeyCode::Char('d') => Some(Key::Right),
+        KeyCode::Char(' ') => Some(Key::Return),
+        _ => None,
+    };
+    if let Some(k) = p1 {
+        return Some((0, k));
+    }
+    let p2 = match key {
+        KeyCode::Up => Key::Up,
+        KeyCode::Left => Key::Left,
+        KeyCode::Down => Key::Down,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Return,
+        _ => return None,
+    };
+    // a lone player may use either key set
+    Some((if players == 1 { 0 } else { 1 }, p2))
+}
+
+fn draw(out: &mut impl Write, game: &Game) -> crossterm::Result<()> {
+    let board = game.board();
+    queue!(out, cursor::MoveTo(0, 0), style::ResetColor)?;
+    queue!(
+        out,
+        style::Print(format!("pawn_fight  {:6.1}s", game.elapsed())),
+        terminal::Clear(terminal::ClearType::UntilNewLine),
+        cursor::MoveToNextLine(2)
+    )?;
+
+    let zones = board.rules().zones(board);
+    let collapsing = board.collapse_warning().map(|(ring, _left)| ring);
+    let mut cursors = Vec::new();
+    let mut selected = Vec::new();
+    for p in 0..board.num_players() {
+        if let Some(human) = game.human(p) {
+            cursors.extend(human.cursor());
+            selected.extend(human.selected());
+        }
+    }
+
+    for row in 0..board.rows {
+        for col in 0..board.cols {
+            let loc = (col, row);
+            let bg = if selected.contains(&loc) {
+                color::BLUE
+            } else if cursors.contains(&loc) {
+                color::BRIGHTBLUE
+            } else {
+                let bg = zones.iter().filter(|(at, _tint)| *at == loc).fold(
+                    board.cell_color(loc).unwrap_or(color::BLACK),
+                    |bg, (_at, tint)| blend(bg, *tint),
+                );
+                match collapsing {
+                    Some(ring) if board.ring(loc) == ring && board.cell(loc) != Cell::Wall => {
+                        blend(
+                            bg,
+                            [color::RED[0], color::RED[1], color::RED[2], COLLAPSE_ALPHA],
+                        )
+                    }
+                    _ => bg,
+                }
+            };
+            queue!(out, style::SetBackgroundColor(rgb(bg)))?;
+            match board.token_at(loc) {
+                Some(t) => {
+                    let (state_color, mark) = match t.state {
+                        TokenStates::Wait => (color::RED, ' '),
+                        TokenStates::Prep => (color::YELLOW, ' '),
+                        TokenStates::Ready => (color::BRIGHTGREEN, '!'),
+                        TokenStates::Dead => (color::DARKGREY, ' '),
+                    };
+                    let glyph = match t.class() {
+                        TokenClass::Pawn => "●",
+                        TokenClass::Runner => "◆",
+                        TokenClass::Jumper => "▲",
+                        TokenClass::Guard => "■",
+                    };
+                    let crown = if t.promoted() { "+" } else { " " };
+                    let countdown = (t.time_left().ceil() as u32).min(9);
+                    queue!(
+                        out,
+                        style::SetForegroundColor(rgb(color::GOLD)),
+                        style::Print(crown),
+                        style::SetForegroundColor(rgb(t.color())),
+                        style::Print(glyph),
+                        style::SetForegroundColor(rgb(state_color)),
+                        style::Print(format!("{}{}", countdown, mark)),
+                    )?;
+                }
+                None if board.cell(loc) == Cell::Wall => {
+                    queue!(
+                        out,
+                        style::SetForegroundColor(rgb(color::LIGHTGREY)),
+                        style::Print("▒".repeat(CELL_WIDTH))
+                    )?;
+                }
+                None => queue!(out, style::Print(" ".repeat(CELL_WIDTH)))?,
+            }
+        }
+        queue!(
+            out,
+            style::ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+
+    queue!(out, cursor::MoveToNextLine(1))?;
+    for summary in game.player_summaries() {
+        queue!(
+            out,
+            style::SetForegroundColor(rgb(summary.color)),
+            style::Print(format!(
+                "{}: {} tokens, {} kills",
+                summary.name, summary.tokens, summary.kills
+            )),
+            style::ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    let status = match game.outcome_text() {
+        Some(text) => format!("{}! q to quit", text),
+        None => String::from("P1: WASD + Space   P2: arrows + Enter   q: quit"),
+    };
+    if let Some(mode) = game.mode_status() {
+        queue!(
+            out,
+            style::Print(mode),
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    if let Some(announcement) = game.announcement() {
+        queue!(
+            out,
+            style::SetForegroundColor(rgb(color::GOLD)),
+            style::Print(announcement),
+            style::ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    if let Some(collapse) = game.collapse_status() {
+        queue!(
+            out,
+            style::SetForegroundColor(rgb(color::RED)),
+            style::Print(collapse),
+            style::ResetColor,
+            terminal::Clear(terminal::ClearType::UntilNewLine),
+            cursor::MoveToNextLine(1)
+        )?;
+    }
+    queue!(
+        out,
+        style::Print(status),
+        terminal::Clear(terminal::ClearType::UntilNewLine),
+        cursor::MoveToNextLine(1),
+        style::SetForegroundColor(rgb(color::LIGHTGREY)),
+        style::Print("digit: seconds left  red wait, yellow prep, green ready (!)  +: promoted"),
+        style::ResetColor,
+        terminal::Clear(terminal::ClearType::UntilNewLine),
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+fn run(players: i32, options: MatchOptions) -> crossterm::Result<()> {
+    let seats = (0..players)
+        .map(|p| HumanPlayer::new(p, InputTypes::Keyboard))
+        .collect();
+    let mut game = Game::with_options(seats, options);
+
+    let mut out = io::stdout();
+    let _screen = RawScreen::enter(&mut out)?;
+    queue!(out, terminal::Clear(terminal::ClearType::All))?;
+
+    let tick = Duration::from_secs_f64(TICK);
+    let mut next_tick = Instant::now();
+    let mut since_redraw = REDRAW_EVERY;
+    loop {
+        let now = Instant::now();
+        if now < next_tick {
+            if event::poll(next_tick - now)? {
+                match event::read()? {
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('c'),
+                        modifiers: KeyModifiers::CONTROL,
+                    })
+                    | Event::Key(KeyEvent {
+                        code: KeyCode::Char('q'),
+                        ..
+                    })
+                    | Event::Key(KeyEvent {
+                        code: KeyCode::Esc, ..
+                    }) => return Ok(()),
+                    Event::Key(KeyEvent { code, .. }) => {
+                        if let Some((p, key)) = route_key(code, players) {
+                            game.handle_key_press_for(p, key);
+                        }
+                    }
+                    Event::Resize(..) => queue!(out, terminal::Clear(terminal::ClearType::All))?,
+                    Event::Mouse(_) => {}
+                }
+            }
+            continue;
+        }
+        next_tick += tick;
+        game.update(TICK);
+        since_redraw += 1;
+        if since_redraw >= REDRAW_EVERY {
+            draw(&mut out, &game)?;
+            since_redraw = 0;
+        }
+    }
+}
+
+fn main() {
+    let (players, options) = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = run(players, options) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
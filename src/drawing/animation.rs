@@ -0,0 +1,256 @@
+use std::time::Instant;
+
+use piston_window::{CircleArc, Context, Ellipse, Graphics};
+
+use crate::drawing::Layout;
+use crate::game_objects::{BoardEvent, TokenId};
+
+const SLIDE_TIME: f64 = 0.15;
+const DEATH_TIME: f64 = 0.4;
+const PULSE_TIME: f64 = 0.3;
+const PULSE_SCALE: f64 = 0.2;
+const BURST_SCALE: f64 = 1.6;
+
+struct Slide {
+    id: TokenId,
+    from: (i32, i32),
+    to: (i32, i32),
+    start: f64,
+}
+
+struct Death {
+    at: (i32, i32),
+    color: [f32; 4],
+    start: f64,
+}
+
+struct Pulse {
+    id: TokenId,
+    start: f64,
+}
+
+fn smoothstep(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Purely cosmetic token animations. The board changes instantly; the animator is fed the
+/// resulting `BoardEvent`s and runs on the wall clock, so it never affects simulation timing.
+pub struct Animator {
+    epoch: Instant,
+    now: f64,
+    slides: Vec<Slide>,
+    deaths: Vec<Death>,
+    pulses: Vec<Pulse>,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Animator::new()
+    }
+}
+
+impl Animator {
+    pub fn new() -> Animator {
+        Animator {
+            epoch: Instant::now(),
+            now: 0.0,
+            slides: Vec::new(),
+            deaths: Vec::new(),
+            pulses: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, event: BoardEvent) {
+        let now = self.epoch.elapsed().as_secs_f64();
+        self.push_at(event, now);
+    }
+
+    fn push_at(&mut self, event: BoardEvent, now: f64) {
+        match event {
+            BoardEvent::Moved { id, from, to } => {
+                self.slides.retain(|s| s.id != id);
+                self.slides.push(Slide {
+                    id,
+                    from,
+                    to,
+                    start: now,
+                });
+            }
+            BoardEvent::Killed { at, color, .. } => {
+                self.slides.retain(|s| s.to != at);
+                self.deaths.push(Death {
+                    at,
+                    color,
+                    start: now,
+                })
+            }
+            BoardEvent::Ready { id, .. } | BoardEvent::Promoted { id, .. } => {
+                self.pulses.push(Pulse { id, start: now })
+            }
+        }
+    }
+
+    /// Advances to the current wall-clock time and drops finished animations.
+    pub fn tick(&mut self) {
+        let now = self.epoch.elapsed().as_secs_f64();
+        self.tick_at(now);
+    }
+
+    fn tick_at(&mut self, now: f64) {
+        self.now = now;
+        self.slides.retain(|s| now - s.start < SLIDE_TIME);
+        self.deaths.retain(|d| now - d.start < DEATH_TIME);
+        self.pulses.retain(|p| now - p.start < PULSE_TIME);
+    }
+
+    /// Where token `id`, standing at `pos`, should be drawn this frame.
+    pub fn token_rect(&self, layout: &Layout, id: TokenId, pos: (i32, i32)) -> [f64; 4] {
+        let mut rect = layout.token_rect(pos);
+        if let Some(s) = self.slides.iter().find(|s| s.id == id) {
+            let from = layout.token_rect(s.from);
+            let t = smoothstep((self.now - s.start) / SLIDE_TIME);
+            rect[0] = from[0] + (rect[0] - from[0]) * t;
+            rect[1] = from[1] + (rect[1] - from[1]) * t;
+        }
+        if let Some(p) = self.pulses.iter().find(|p| p.id == id) {
+            let t = (self.now - p.start) / PULSE_TIME;
+            let grow = rect[2] * PULSE_SCALE * (t * std::f64::consts::PI).sin().max(0.0);
+            rect = [
+                rect[0] - grow / 2.0,
+                rect[1] - grow / 2.0,
+                rect[2] + grow,
+                rect[3] + grow,
+            ];
+        }
+        rect
+    }
+
+    /// Draws captured tokens shrinking away inside an expanding burst ring.
+    pub fn draw_effects<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
+        for d in self.deaths.iter() {
+            let t = smoothstep((self.now - d.start) / DEATH_TIME);
+            let base = layout.token_rect(d.at);
+            let center = [base[0] + base[2] / 2.0, base[1] + base[3] / 2.0];
+            let square = |size: f64| [center[0] - size / 2.0, center[1] - size / 2.0, size, size];
+            let fading = [
+                d.color[0],
+                d.color[1],
+                d.color[2],
+                d.color[3] * (1.0 - t) as f32,
+            ];
+            Ellipse::new(fading).draw(square(base[2] * (1.0 - t)), &c.draw_state, c.transform, g);
+            CircleArc::new(fading, 3.0, 0.0, 1.9999 * std::f64::consts::PI).draw(
+                square(base[2] * (1.0 + (BURST_SCALE - 1.0) * t)),
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drawing::color;
+
+    const TOKEN: TokenId = TokenId(7);
+
+    fn layout() -> Layout {
+        Layout::fit([400.0, 400.0], 0.0, 4, 4)
+    }
+
+    fn moved(from: (i32, i32), to: (i32, i32)) -> BoardEvent {
+        BoardEvent::Moved {
+            id: TOKEN,
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn slides_run_from_the_old_cell_to_the_new_one() {
+        let layout = layout();
+        let (from, to) = ((0, 0), (2, 0));
+        let mut animator = Animator::new();
+        animator.push_at(moved(from, to), 0.0);
+
+        animator.tick_at(0.0);
+        assert_eq!(
+            animator.token_rect(&layout, TOKEN, to),
+            layout.token_rect(from)
+        );
+        animator.tick_at(SLIDE_TIME / 2.0);
+        let (start, end) = (layout.token_rect(from), layout.token_rect(to));
+        let mid = animator.token_rect(&layout, TOKEN, to);
+        assert!((mid[0] - (start[0] + end[0]) / 2.0).abs() < 1e-9);
+        assert_eq!(mid[1], end[1]);
+        // other tokens are not moved
+        assert_eq!(animator.token_rect(&layout, TokenId(8), to), end);
+
+        animator.tick_at(SLIDE_TIME);
+        assert!(animator.slides.is_empty());
+        assert_eq!(animator.token_rect(&layout, TOKEN, to), end);
+    }
+
+    #[test]
+    fn a_capture_ends_the_victims_slide_and_leaves_a_burst() {
+        let mut animator = Animator::new();
+        animator.push_at(moved((0, 0), (1, 1)), 0.0);
+        animator.push_at(
+            BoardEvent::Killed {
+                id: TOKEN,
+                at: (1, 1),
+                color: color::RED,
+            },
+            0.05,
+        );
+        assert!(animator.slides.is_empty());
+        assert_eq!(animator.deaths.len(), 1);
+        animator.tick_at(0.05 + DEATH_TIME * 0.9);
+        assert_eq!(animator.deaths.len(), 1);
+        animator.tick_at(0.05 + DEATH_TIME);
+        assert!(animator.deaths.is_empty());
+    }
+
+    #[test]
+    fn pulses_swell_and_settle() {
+        let layout = layout();
+        let at = (1, 2);
+        let base = layout.token_rect(at);
+        let mut animator = Animator::new();
+        animator.push_at(BoardEvent::Ready { id: TOKEN, at }, 0.0);
+        animator.push_at(
+            BoardEvent::Promoted {
+                id: TokenId(8),
+                at: (3, 3),
+                owner: 0,
+            },
+            0.0,
+        );
+        assert_eq!(animator.pulses.len(), 2);
+
+        animator.tick_at(0.0);
+        assert_eq!(animator.token_rect(&layout, TOKEN, at), base);
+        animator.tick_at(PULSE_TIME / 2.0);
+        let swollen = animator.token_rect(&layout, TOKEN, at);
+        let grow = base[2] * PULSE_SCALE;
+        assert!((swollen[2] - (base[2] + grow)).abs() < 1e-9);
+        // it grows about its center
+        assert!((swollen[0] + swollen[2] / 2.0 - (base[0] + base[2] / 2.0)).abs() < 1e-9);
+
+        animator.tick_at(PULSE_TIME);
+        assert!(animator.pulses.is_empty());
+        assert_eq!(animator.token_rect(&layout, TOKEN, at), base);
+    }
+
+    #[test]
+    fn a_new_move_replaces_the_tokens_slide() {
+        let mut animator = Animator::new();
+        animator.push_at(moved((0, 0), (1, 0)), 0.0);
+        animator.push_at(moved((1, 0), (2, 0)), 0.1);
+        assert_eq!(animator.slides.len(), 1);
+        assert_eq!(animator.slides[0].from, (1, 0));
+    }
+}
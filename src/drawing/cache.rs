@@ -0,0 +1,188 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use piston_window::{Context, G2d, G2dTexture, G2dTextureContext, Image, TextureSettings};
+
+use crate::drawing::software::SoftwareGraphics;
+use crate::drawing::Layout;
+use crate::game_objects::{Board, TokenClass};
+
+fn color_key(color: [f32; 4]) -> [u8; 4] {
+    [
+        (color[0] * 255.0) as u8,
+        (color[1] * 255.0) as u8,
+        (color[2] * 255.0) as u8,
+        (color[3] * 255.0) as u8,
+    ]
+}
+
+// the background's size in pixels, the board's size and its terrain
+type BackgroundKey = (u32, u32, i32, i32, u64);
+
+// pixels per point, so cached textures stay sharp on HiDPI displays
+fn pixel_scale(c: &Context) -> f64 {
+    match c.viewport {
+        Some(v) if v.window_size[0] > 0.0 => v.draw_size[0] as f64 / v.window_size[0],
+        _ => 1.0,
+    }
+}
+
+/// GPU textures for geometry that rarely changes: the terrain and grid are rendered once per
+/// layout and terrain, and token bodies once per size, color and shape, instead of being
+/// re-tessellated every frame. Both are rasterized with `SoftwareGraphics` from the regular
+/// drawing code.
+#[derive(Default)]
+pub struct RenderCache {
+    background_key: Option<BackgroundKey>,
+    background: Option<G2dTexture>,
+    bodies: HashMap<(u32, [u8; 4], TokenClass), G2dTexture>,
+}
+
+impl RenderCache {
+    pub fn new() -> RenderCache {
+        RenderCache::default()
+    }
+
+    /// What the background for `board` drawn at `layout` depends on; `None` when it would be
+    /// empty. The board's position in the window is not part of it, as the texture is drawn
+    /// wherever the layout puts the board.
+    fn background_key(board: &Board, layout: &Layout, scale: f64) -> Option<BackgroundKey> {
+        let rect = layout.board_rect();
+        let (w, h) = (
+            (rect[2] * scale).round() as u32,
+            (rect[3] * scale).round() as u32,
+        );
+        if w == 0 || h == 0 {
+            return None;
+        }
+        Some((w, h, board.cols, board.rows, board.terrain_key()))
+    }
+
+    /// Records `key` as the background's, returning true if the cached one was made for
+    /// another and has to be rebuilt.
+    fn replace_background_key(&mut self, key: BackgroundKey) -> bool {
+        if self.background_key == Some(key) {
+            return false;
+        }
+        self.background_key = Some(key);
+        // token sizes follow the layout, so old bodies will not be asked for again
+        self.bodies.clear();
+        true
+    }
+
+    pub fn draw_background(
+        &mut self,
+        c: &Context,
+        g: &mut G2d<'_>,
+        tc: &mut G2dTextureContext,
+        board: &Board,
+        layout: &Layout,
+    ) {
+        let scale = pixel_scale(c);
+        let key = match RenderCache::background_key(board, layout, scale) {
+            Some(key) => key,
+            None => return,
+        };
+        if self.replace_background_key(key) {
+            let (w, h, _cols, _rows, _terrain) = key;
+            let mut soft = SoftwareGraphics::new(w, h);
+            let local = Layout {
+                origin: [0.0, 0.0],
+                cell: layout.cell * scale,
+                cols: layout.cols,
+                rows: layout.rows,
+            };
+            board.draw_cells(&soft.context(), &mut soft, &local);
+            self.background =
+                G2dTexture::from_image(tc, &soft.finish(), &TextureSettings::new()).ok();
+        }
+        if let Some(texture) = self.background.as_ref() {
+            Image::new()
+                .rect(layout.board_rect())
+                .draw(texture, &c.draw_state, c.transform, g);
+        }
+    }
+
+    /// Draws a filled token of `class`'s shape in `color` inside `rect`, rendering it to a
+    /// texture at the layout's token size the first time that size, color and shape are seen.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_body(
+        &mut self,
+        c: &Context,
+        g: &mut G2d<'_>,
+        tc: &mut G2dTextureContext,
+        layout: &Layout,
+        class: TokenClass,
+        color: [f32; 4],
+        rect: [f64; 4],
+    ) {
+        let size = (layout.token_rect((0, 0))[2] * pixel_scale(c)).ceil() as u32;
+        if size == 0 {
+            return;
+        }
+        let key = (size, color_key(color), class);
+        let texture = match self.bodies.entry(key) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let mut soft = SoftwareGraphics::new(size, size);
+                let sc = soft.context();
+                class.draw_body(color, [0.0, 0.0, size as f64, size as f64], &sc, &mut soft);
+                match G2dTexture::from_image(tc, &soft.finish(), &TextureSettings::new()) {
+                    Ok(texture) => e.insert(texture),
+                    Err(_) => return,
+                }
+            }
+        };
+        Image::new()
+            .rect(rect)
+            .draw(texture, &c.draw_state, c.transform, g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::SuddenDeath;
+
+    fn rebuilds(cache: &mut RenderCache, board: &Board, layout: &Layout, scale: f64) -> bool {
+        let key = RenderCache::background_key(board, layout, scale).unwrap();
+        cache.replace_background_key(key)
+    }
+
+    #[test]
+    fn the_background_is_reused_until_its_inputs_change() {
+        let mut board = Board::new(2);
+        let layout = Layout::fit([800.0, 600.0], 40.0, board.cols, board.rows);
+        let mut cache = RenderCache::new();
+        assert!(rebuilds(&mut cache, &board, &layout, 1.0));
+        assert!(!rebuilds(&mut cache, &board, &layout, 1.0));
+
+        // a taller HUD band moves the board without resizing it
+        let moved = Layout::fit([900.0, 640.0], 80.0, board.cols, board.rows);
+        assert_eq!(moved.cell, layout.cell);
+        assert!(!rebuilds(&mut cache, &board, &moved, 1.0));
+
+        let resized = Layout::fit([1000.0, 800.0], 40.0, board.cols, board.rows);
+        assert!(rebuilds(&mut cache, &board, &resized, 1.0));
+        assert!(!rebuilds(&mut cache, &board, &resized, 1.0));
+        // a HiDPI display needs more pixels for the same layout
+        assert!(rebuilds(&mut cache, &board, &resized, 2.0));
+
+        board.set_sudden_death(Some(SuddenDeath {
+            start: 0.0,
+            every: 1.0,
+            warning: 0.5,
+        }));
+        board.update(0.1);
+        assert_eq!(board.collapsed_rings(), 1);
+        assert!(rebuilds(&mut cache, &board, &resized, 2.0));
+        assert!(!rebuilds(&mut cache, &board, &resized, 2.0));
+    }
+
+    #[test]
+    fn an_empty_board_area_has_no_background() {
+        let board = Board::new(2);
+        let layout = Layout::fit([800.0, 40.0], 40.0, board.cols, board.rows);
+        assert_eq!(RenderCache::background_key(&board, &layout, 1.0), None);
+    }
+}
@@ -0,0 +1,68 @@
+use std::f64::consts;
+use std::fs::File;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+use piston_window::CircleArc;
+
+use crate::drawing::{color, render_board_with};
+use crate::game_objects::BoardEvent;
+use crate::replay::Replay;
+
+/// How long, in ticks, a capture stays marked in the exported frames.
+const CAPTURE_MARK_TICKS: u64 = 45;
+
+/// Re-simulates a replay and writes every `every`-th tick as a frame of an animated GIF, along
+/// with the tick of every capture so none fall between frames. Captured cells are ringed in
+/// red for a short while afterwards.
+pub fn export_gif(replay: &Replay, every: u64, size: u32, path: &str) -> Result<(), String> {
+    let every = every.max(1);
+    let file = File::create(path).map_err(|e| format!("could not create {}: {}", path, e))?;
+    let mut encoder = GifEncoder::new(file);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+    let delay_ms = (replay.tick_seconds * every as f64 * 1000.0).round() as u32;
+
+    let mut playback = replay.playback();
+    let mut captures: Vec<(u64, (i32, i32))> = Vec::new();
+    let mut since_frame = every;
+    while !playback.is_finished() {
+        let events = playback.step();
+        let tick = playback.tick();
+        let mut captured = false;
+        for event in events {
+            if let BoardEvent::Killed { at, .. } = event {
+                captures.push((tick, at));
+                captured = true;
+            }
+        }
+        captures.retain(|(t, _)| tick - t < CAPTURE_MARK_TICKS);
+        since_frame += 1;
+        if since_frame < every && !captured && !playback.is_finished() {
+            continue;
+        }
+        since_frame = 0;
+        let image = render_board_with(playback.board(), &[], size, size, |c, g, layout| {
+            for (t, at) in captures.iter() {
+                let age = (tick - t) as f64 / CAPTURE_MARK_TICKS as f64;
+                let r = layout.centered_rect(*at, 0.6 + 0.5 * age);
+                CircleArc::new(color::RED, 3.0, 0.0, 1.9999 * consts::PI).draw(
+                    r,
+                    &c.draw_state,
+                    c.transform,
+                    g,
+                );
+            }
+        });
+        encoder
+            .encode_frame(Frame::from_parts(
+                image,
+                0,
+                0,
+                Delay::from_numer_denom_ms(delay_ms, 1),
+            ))
+            .map_err(|e| format!("could not write {}: {}", path, e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use image::RgbaImage;
+use piston_window::{clear, Context};
+
+use crate::drawing::{color, Layout, SoftwareGraphics};
+use crate::game_objects::{draw_selector, Board, Position};
+
+/// Renders a board the way the game window shows it, without a window or GPU: the cells,
+/// tokens with their state arcs, and selection rings, scaled to fit `width` x `height`.
+/// `overlay` runs last with the same context and layout, for annotations.
+pub fn render_board_with<F>(
+    board: &Board,
+    selections: &[(i32, i32)],
+    width: u32,
+    height: u32,
+    overlay: F,
+) -> RgbaImage
+where
+    F: FnOnce(&Context, &mut SoftwareGraphics, &Layout),
+{
+    let mut g = SoftwareGraphics::new(width, height);
+    let c = g.context();
+    let layout = Layout::fit([width as f64, height as f64], 0.0, board.cols, board.rows);
+    clear(color::BLACK, &mut g);
+    board.draw_cells(&c, &mut g, &layout);
+    board.draw_tokens(&c, &mut g, &layout);
+    for sel in selections.iter() {
+        draw_selector(&c, &mut g, &layout, *sel);
+    }
+    overlay(&c, &mut g, &layout);
+    g.finish()
+}
+
+pub fn render_position(position: &Position, width: u32, height: u32) -> RgbaImage {
+    render_board_with(
+        &position.board,
+        &position.selections,
+        width,
+        height,
+        |_c, _g, _layout| {},
+    )
+}
+
+/// Renders a position and writes it out as a PNG.
+pub fn save_png(position: &Position, width: u32, height: u32, path: &str) -> Result<(), String> {
+    render_position(position, width, height)
+        .save(path)
+        .map_err(|e| format!("could not write {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/position.png");
+    /// Largest per-channel difference still counted as the same pixel.
+    const TOLERANCE: i32 = 2;
+
+    const POSITION: &str = "\
+0r . . 1w:2/4
+. 0pR:3 . .
+. . 1rJ+ .
+0w:1/4 . . 1pG:6
+select 1 1
+";
+
+    #[test]
+    fn position_matches_golden_image() {
+        let position = Position::parse(POSITION).unwrap();
+        let image = render_position(&position, 160, 160);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            image.save(GOLDEN).unwrap();
+        }
+        let golden = image::open(GOLDEN)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", GOLDEN, e))
+            .to_rgba8();
+        assert_eq!(image.dimensions(), golden.dimensions());
+        let differing = image
+            .pixels()
+            .zip(golden.pixels())
+            .filter(|(a, b)| {
+                a.0.iter()
+                    .zip(b.0.iter())
+                    .any(|(x, y)| (*x as i32 - *y as i32).abs() > TOLERANCE)
+            })
+            .count();
+        assert_eq!(
+            differing, 0,
+            "{} pixels differ from {}; rerun with UPDATE_GOLDEN=1 if the change is intended",
+            differing, GOLDEN
+        );
+    }
+}
@@ -0,0 +1,146 @@
+/// Maps board cells to window coordinates. The board keeps square cells and is scaled to fit
+/// the area below the HUD band, centered with letterboxing on whichever axis has room to spare.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    pub origin: [f64; 2],
+    pub cell: f64,
+    pub cols: i32,
+    pub rows: i32,
+}
+
+const TOKEN_SCALE: f64 = 0.6;
+const SELECTOR_SCALE: f64 = 0.85;
+
+impl Layout {
+    /// Fits a `cols` x `rows` board into a window of `window_size` points, leaving `top` points
+    /// free above it.
+    pub fn fit(window_size: [f64; 2], top: f64, cols: i32, rows: i32) -> Layout {
+        let avail_w = window_size[0].max(0.0);
+        let avail_h = (window_size[1] - top).max(0.0);
+        let cell = (avail_w / cols as f64).min(avail_h / rows as f64);
+        Layout {
+            origin: [
+                (avail_w - cell * cols as f64) / 2.0,
+                top + (avail_h - cell * rows as f64) / 2.0,
+            ],
+            cell,
+            cols,
+            rows,
+        }
+    }
+
+    pub fn board_rect(&self) -> [f64; 4] {
+        [
+            self.origin[0],
+            self.origin[1],
+            self.cell * self.cols as f64,
+            self.cell * self.rows as f64,
+        ]
+    }
+
+    pub fn cell_rect(&self, loc: (i32, i32)) -> [f64; 4] {
+        [
+            self.origin[0] + loc.0 as f64 * self.cell,
+            self.origin[1] + loc.1 as f64 * self.cell,
+            self.cell,
+            self.cell,
+        ]
+    }
+
+    /// The square a circle of `scale` times the cell size occupies, centered in the cell.
+    pub fn centered_rect(&self, loc: (i32, i32), scale: f64) -> [f64; 4] {
+        let size = self.cell * scale;
+        let r = self.cell_rect(loc);
+        [
+            r[0] + (self.cell - size) / 2.0,
+            r[1] + (self.cell - size) / 2.0,
+            size,
+            size,
+        ]
+    }
+
+    pub fn token_rect(&self, loc: (i32, i32)) -> [f64; 4] {
+        self.centered_rect(loc, TOKEN_SCALE)
+    }
+
+    pub fn selector_rect(&self, loc: (i32, i32)) -> [f64; 4] {
+        self.centered_rect(loc, SELECTOR_SCALE)
+    }
+
+    /// The cell under a window position, if it lies on the board.
+    pub fn cell_at(&self, pos: [f64; 2]) -> Option<(i32, i32)> {
+        if self.cell <= 0.0 {
+            return None;
+        }
+        let x = ((pos[0] - self.origin[0]) / self.cell).floor();
+        let y = ((pos[1] - self.origin[1]) / self.cell).floor();
+        if x < 0.0 || y < 0.0 || x >= self.cols as f64 || y >= self.rows as f64 {
+            return None;
+        }
+        Some((x as i32, y as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_windows_letterbox_left_and_right() {
+        let layout = Layout::fit([1000.0, 400.0], 0.0, 4, 4);
+        assert_eq!(layout.cell, 100.0);
+        assert_eq!(layout.origin, [300.0, 0.0]);
+        assert_eq!(layout.board_rect(), [300.0, 0.0, 400.0, 400.0]);
+    }
+
+    #[test]
+    fn tall_windows_letterbox_above_and_below() {
+        let layout = Layout::fit([400.0, 1000.0], 0.0, 4, 4);
+        assert_eq!(layout.cell, 100.0);
+        assert_eq!(layout.origin, [0.0, 300.0]);
+    }
+
+    #[test]
+    fn the_hud_band_is_left_free() {
+        let layout = Layout::fit([600.0, 500.0], 100.0, 6, 2);
+        assert_eq!(layout.cell, 100.0);
+        assert_eq!(layout.origin, [0.0, 200.0]);
+        assert_eq!(layout.cell_at([50.0, 150.0]), None);
+        assert_eq!(layout.cell_at([50.0, 250.0]), Some((0, 0)));
+    }
+
+    #[test]
+    fn clicks_in_the_letterbox_miss_the_board() {
+        let layout = Layout::fit([1000.0, 400.0], 0.0, 4, 4);
+        for pos in [[0.0, 200.0], [299.9, 200.0], [700.0, 200.0], [999.0, 0.0]] {
+            assert_eq!(layout.cell_at(pos), None, "{:?}", pos);
+        }
+        assert_eq!(layout.cell_at([300.0, 0.0]), Some((0, 0)));
+        assert_eq!(layout.cell_at([699.9, 399.9]), Some((3, 3)));
+    }
+
+    #[test]
+    fn cell_rects_map_back_to_their_cells() {
+        let layout = Layout::fit([1280.0, 800.0], 40.0, 7, 5);
+        for col in 0..7 {
+            for row in 0..5 {
+                let r = layout.cell_rect((col, row));
+                assert_eq!(layout.cell_at([r[0], r[1]]), Some((col, row)));
+                let centre = [r[0] + r[2] / 2.0, r[1] + r[3] / 2.0];
+                assert_eq!(layout.cell_at(centre), Some((col, row)));
+                let t = layout.token_rect((col, row));
+                assert_eq!(layout.cell_at([t[0], t[1]]), Some((col, row)));
+            }
+        }
+    }
+
+    #[test]
+    fn zero_size_windows_have_no_cells() {
+        for size in [[0.0, 0.0], [800.0, 0.0], [0.0, 600.0], [800.0, 30.0]] {
+            let layout = Layout::fit(size, 40.0, 8, 8);
+            assert_eq!(layout.cell, 0.0, "{:?}", size);
+            assert_eq!(layout.cell_at([0.0, 40.0]), None);
+            assert_eq!(layout.cell_at([size[0] / 2.0, size[1] / 2.0]), None);
+        }
+    }
+}
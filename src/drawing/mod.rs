@@ -1,3 +1,19 @@
+mod animation;
+mod cache;
+mod gif;
+mod headless;
+mod layout;
+mod software;
+mod svg;
+
+pub use self::animation::Animator;
+pub use self::cache::RenderCache;
+pub use self::gif::export_gif;
+pub use self::headless::{render_board_with, render_position, save_png};
+pub use self::layout::Layout;
+pub use self::software::{SoftwareGraphics, SoftwareTexture};
+pub use self::svg::{render_svg, Arrow};
+
 #[allow(unused)]
 pub mod color {
     pub const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
@@ -8,16 +24,20 @@ pub mod color {
     pub const ORANGE: [f32; 4] = [1.0, 0.5, 0.0, 1.0];
     pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
     pub const VIOLET: [f32; 4] = [0.6, 0.0, 1.0, 1.0];
+    pub const GOLD: [f32; 4] = [0.75, 0.6, 0.0, 1.0];
+    pub const TEAL: [f32; 4] = [0.0, 0.5, 0.5, 1.0];
     pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
     pub const GREEN: [f32; 4] = [0.047, 0.55, 0.15, 1.0];
     pub const BRIGHTGREEN: [f32; 4] = [0.047, 0.95, 0.15, 1.0];
     pub const YELLOW: [f32; 4] = [0.9, 0.9, 0.15, 1.0];
+    pub const CYAN: [f32; 4] = [0.0, 0.8, 0.9, 1.0];
 }
 
 pub mod screen {
     pub const WIDTH: i64 = 768;
     pub const HEIGHT: i64 = 768;
     pub const SIZE: i32 = 10;
+    pub const HUD_HEIGHT: i64 = 96;
 }
 
 pub mod token {
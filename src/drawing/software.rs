@@ -0,0 +1,186 @@
+use image::{Rgba, RgbaImage};
+use piston_window::{Context, DrawState, Graphics, ImageSize};
+
+/// Edge length of the supersampling grid used for antialiasing.
+const SAMPLES: u32 = 3;
+
+/// An RGBA image usable as a texture by `SoftwareGraphics`.
+pub struct SoftwareTexture(pub RgbaImage);
+
+impl ImageSize for SoftwareTexture {
+    fn get_size(&self) -> (u32, u32) {
+        self.0.dimensions()
+    }
+}
+
+/// A CPU implementation of piston's `Graphics`, so the same drawing code that targets the
+/// OpenGL window can render into an image with no GPU. Triangles are rasterized on a
+/// supersampled buffer and box-filtered down when the image is taken with `finish`.
+pub struct SoftwareGraphics {
+    width: u32,
+    height: u32,
+    buffer: Vec<[f32; 4]>,
+}
+
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+impl SoftwareGraphics {
+    pub fn new(width: u32, height: u32) -> SoftwareGraphics {
+        SoftwareGraphics {
+            width,
+            height,
+            buffer: vec![[0.0; 4]; (width * SAMPLES * height * SAMPLES) as usize],
+        }
+    }
+
+    /// A context whose coordinates are image pixels, origin top left.
+    pub fn context(&self) -> Context {
+        Context::new_abs(self.width as f64, self.height as f64)
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let i = (y * self.width * SAMPLES + x) as usize;
+        let dst = self.buffer[i];
+        let a = color[3];
+        let out_a = a + dst[3] * (1.0 - a);
+        if out_a <= 0.0 {
+            return;
+        }
+        let mut out = [0.0; 4];
+        for c in 0..3 {
+            out[c] = (color[c] * a + dst[c] * dst[3] * (1.0 - a)) / out_a;
+        }
+        out[3] = out_a;
+        self.buffer[i] = out;
+    }
+
+    // vertices arrive in normalized device coordinates
+    fn to_buffer(&self, v: [f32; 2]) -> [f32; 2] {
+        [
+            (v[0] + 1.0) * 0.5 * (self.width * SAMPLES) as f32,
+            (1.0 - v[1]) * 0.5 * (self.height * SAMPLES) as f32,
+        ]
+    }
+
+    fn fill_triangle<F>(&mut self, tri: [[f32; 2]; 3], mut shade: F)
+    where
+        F: FnMut([f32; 3]) -> Option<[f32; 4]>,
+    {
+        let [a, b, c] = [
+            self.to_buffer(tri[0]),
+            self.to_buffer(tri[1]),
+            self.to_buffer(tri[2]),
+        ];
+        let area = edge(a, b, c);
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+        let (bw, bh) = (
+            (self.width * SAMPLES) as f32,
+            (self.height * SAMPLES) as f32,
+        );
+        let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+        let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+        let max_x = a[0].max(b[0]).max(c[0]).ceil().min(bw) as u32;
+        let max_y = a[1].max(b[1]).max(c[1]).ceil().min(bh) as u32;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                let w0 = edge(b, c, p) / area;
+                let w1 = edge(c, a, p) / area;
+                let w2 = edge(a, b, p) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                if let Some(color) = shade([w0, w1, w2]) {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Resolves the supersampled buffer into the final image.
+    pub fn finish(&self) -> RgbaImage {
+        let mut out = RgbaImage::new(self.width, self.height);
+        let n = (SAMPLES * SAMPLES) as f32;
+        for (x, y, px) in out.enumerate_pixels_mut() {
+            let mut sum = [0.0f32; 4];
+            for sy in 0..SAMPLES {
+                for sx in 0..SAMPLES {
+                    let i = ((y * SAMPLES + sy) * self.width * SAMPLES + x * SAMPLES + sx) as usize;
+                    let s = self.buffer[i];
+                    // accumulate premultiplied so transparent samples do not darken edges
+                    for c in 0..3 {
+                        sum[c] += s[c] * s[3];
+                    }
+                    sum[3] += s[3];
+                }
+            }
+            let a = sum[3] / n;
+            let unpremultiply = |c: f32| if sum[3] > 0.0 { c / sum[3] } else { 0.0 };
+            *px = Rgba([
+                (unpremultiply(sum[0]) * 255.0).round() as u8,
+                (unpremultiply(sum[1]) * 255.0).round() as u8,
+                (unpremultiply(sum[2]) * 255.0).round() as u8,
+                (a * 255.0).round() as u8,
+            ]);
+        }
+        out
+    }
+}
+
+impl Graphics for SoftwareGraphics {
+    type Texture = SoftwareTexture;
+
+    fn clear_color(&mut self, color: [f32; 4]) {
+        for px in self.buffer.iter_mut() {
+            *px = color;
+        }
+    }
+
+    fn clear_stencil(&mut self, _value: u8) {}
+
+    fn tri_list<F>(&mut self, _draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        let color = *color;
+        f(&mut |vertices: &[[f32; 2]]| {
+            for tri in vertices.chunks_exact(3) {
+                self.fill_triangle([tri[0], tri[1], tri[2]], |_| Some(color));
+            }
+        });
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        _draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &SoftwareTexture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        let color = *color;
+        let (tw, th) = texture.0.dimensions();
+        f(&mut |vertices: &[[f32; 2]], uvs: &[[f32; 2]]| {
+            for (tri, uv) in vertices.chunks_exact(3).zip(uvs.chunks_exact(3)) {
+                self.fill_triangle([tri[0], tri[1], tri[2]], |w| {
+                    let u = uv[0][0] * w[0] + uv[1][0] * w[1] + uv[2][0] * w[2];
+                    let v = uv[0][1] * w[0] + uv[1][1] * w[1] + uv[2][1] * w[2];
+                    let tx = ((u * tw as f32) as u32).min(tw.saturating_sub(1));
+                    let ty = ((v * th as f32) as u32).min(th.saturating_sub(1));
+                    let t = texture.0.get_pixel(tx, ty).0;
+                    Some([
+                        color[0] * t[0] as f32 / 255.0,
+                        color[1] * t[1] as f32 / 255.0,
+                        color[2] * t[2] as f32 / 255.0,
+                        color[3] * t[3] as f32 / 255.0,
+                    ])
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,287 @@
+use std::f64::consts;
+use std::fmt::Write;
+
+use crate::drawing::{color, Layout};
+use crate::game_objects::Board;
+
+// Line widths as drawn by `Board::draw_cells` and the arrow heads' size relative to a cell.
+const GRID_WIDTH: f64 = 2.0;
+const ARROW_WIDTH: f64 = 4.0;
+const ARROW_HEAD: f64 = 0.22;
+// arcs this close to a full turn have coinciding end points, so they are drawn as circles
+const FULL_RING: f64 = 1.999 * consts::PI;
+
+/// A move to annotate, drawn from the center of one cell to another.
+#[derive(Clone, Copy, Debug)]
+pub struct Arrow {
+    pub from: (i32, i32),
+    pub to: (i32, i32),
+    pub color: [f32; 4],
+}
+
+fn rgb(c: [f32; 4]) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        (c[0] * 255.0).round() as u8,
+        (c[1] * 255.0).round() as u8,
+        (c[2] * 255.0).round() as u8
+    )
+}
+
+fn center(r: [f64; 4]) -> (f64, f64) {
+    (r[0] + r[2] / 2.0, r[1] + r[3] / 2.0)
+}
+
+// An arc along the ellipse inscribed in `r`, clockwise from angle 0 to `end`, as piston's
+// `CircleArc` draws it.
+fn arc_path(r: [f64; 4], end: f64) -> String {
+    let (cx, cy) = center(r);
+    let (rx, ry) = (r[2] / 2.0, r[3] / 2.0);
+    let end = end.min(1.9999 * consts::PI);
+    let large = if end > consts::PI { 1 } else { 0 };
+    format!(
+        "M {:.2} {:.2} A {:.2} {:.2} 0 {} 1 {:.2} {:.2}",
+        cx + rx,
+        cy,
+        rx,
+        ry,
+        large,
+        cx + rx * end.cos(),
+        cy + ry * end.sin()
+    )
+}
+
+/// Renders a board to a standalone SVG document `size` pixels square: the checkerboard,
+/// grid lines, tokens with their state arcs, and optional move arrows on top.
+pub fn render_svg(board: &Board, arrows: &[Arrow], size: u32) -> String {
+    let layout = Layout::fit([size as f64, size as f64], 0.0, board.cols, board.rows);
+    let frame = layout.board_rect();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">",
+        size
+    );
+    out.push_str("<defs>");
+    for (i, arrow) in arrows.iter().enumerate() {
+        let _ = write!(
+            out,
+            "<marker id=\"head{0}\" viewBox=\"0 0 10 10\" refX=\"8\" refY=\"5\" markerUnits=\"userSpaceOnUse\" markerWidth=\"{1:.2}\" markerHeight=\"{1:.2}\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{2}\" fill-opacity=\"{3}\"/></marker>",
+            i,
+            layout.cell * ARROW_HEAD,
+            rgb(arrow.color),
+            arrow.color[3]
+        );
+    }
+    out.push_str("</defs>\n");
+    let _ = writeln!(
+        out,
+        "<rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>",
+        size,
+        rgb(color::BLACK)
+    );
+
+    for row in 0..board.rows {
+        for col in 0..board.cols {
+            if let Some(fill) = board.cell_color((col, row)) {
+                let r = layout.cell_rect((col, row));
+                let _ = writeln!(
+                    out,
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+                    r[0],
+                    r[1],
+                    r[2],
+                    r[3],
+                    rgb(fill)
+                );
+            }
+        }
+    }
+    let grid = rgb(color::LIGHTGREY);
+    for row in 1..board.rows {
+        let y = frame[1] + row as f64 * layout.cell;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{0:.2}\" y1=\"{2:.2}\" x2=\"{1:.2}\" y2=\"{2:.2}\" stroke=\"{3}\" stroke-width=\"{4}\"/>",
+            frame[0],
+            frame[0] + frame[2],
+            y,
+            grid,
+            GRID_WIDTH
+        );
+    }
+    for col in 1..board.cols {
+        let x = frame[0] + col as f64 * layout.cell;
+        let _ = writeln!(
+            out,
+            "<line x1=\"{0:.2}\" y1=\"{1:.2}\" x2=\"{0:.2}\" y2=\"{2:.2}\" stroke=\"{3}\" stroke-width=\"{4}\"/>",
+            x,
+            frame[1],
+            frame[1] + frame[3],
+            grid,
+            GRID_WIDTH
+        );
+    }
+
+    for (pos, t) in board.tokens() {
+        let r = layout.token_rect(pos);
+        // the arc is drawn first and half covered by the body, as in `Token::draw_in`
+        let (cx, cy) = center(r);
+        if let Some((arc_color, radius, end)) = t.state_arc() {
+            if end >= FULL_RING {
+                let _ = writeln!(
+                    out,
+                    "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    cx,
+                    cy,
+                    r[2] / 2.0,
+                    rgb(arc_color),
+                    2.0 * radius
+                );
+            } else if end > 0.0 {
+                let _ = writeln!(
+                    out,
+                    "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    arc_path(r, end),
+                    rgb(arc_color),
+                    2.0 * radius
+                );
+            }
+        }
+        match t.class().outline(r) {
+            Some(points) => {
+                let points: Vec<String> = points
+                    .iter()
+                    .map(|p| format!("{:.2},{:.2}", p[0], p[1]))
+                    .collect();
+                let _ = writeln!(
+                    out,
+                    "<polygon points=\"{}\" fill=\"{}\"/>",
+                    points.join(" "),
+                    rgb(t.color())
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    out,
+                    "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>",
+                    cx,
+                    cy,
+                    r[2] / 2.0,
+                    rgb(t.color())
+                );
+            }
+        }
+        if let Some(mark) = t.promotion_mark(r) {
+            let (mx, my) = center(mark);
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\" stroke=\"{}\"/>",
+                mx,
+                my,
+                mark[2] / 2.0,
+                rgb(color::GOLD),
+                rgb(color::BLACK)
+            );
+        }
+    }
+
+    for (i, arrow) in arrows.iter().enumerate() {
+        let (x1, y1) = center(layout.cell_rect(arrow.from));
+        let (x2, y2) = center(layout.cell_rect(arrow.to));
+        let _ = writeln!(
+            out,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\" marker-end=\"url(#head{})\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            rgb(arrow.color),
+            arrow.color[3],
+            ARROW_WIDTH,
+            i
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    const SIZE: u32 = 300;
+
+    fn sample() -> Board {
+        Position::parse("0r . 1pR:5\n. 0rG 1w:2/4").unwrap().board
+    }
+
+    fn count(svg: &str, part: &str) -> usize {
+        svg.lines().filter(|line| line.contains(part)).count()
+    }
+
+    #[test]
+    fn output_is_a_standalone_document() {
+        let svg = render_svg(&sample(), &[], SIZE);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"300\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<svg").count(), 1);
+    }
+
+    #[test]
+    fn every_shaded_cell_and_grid_line_is_drawn() {
+        let board = sample();
+        let svg = render_svg(&board, &[], SIZE);
+        // the background plus the three shaded cells of a 3 x 2 checkerboard
+        assert_eq!(count(&svg, "<rect"), 4);
+        assert_eq!(count(&svg, &format!("fill=\"{}\"", rgb(color::VIOLET))), 3);
+        let lines = (board.cols - 1 + board.rows - 1) as usize;
+        assert_eq!(count(&svg, "<line"), lines);
+    }
+
+    #[test]
+    fn every_token_is_drawn_in_its_color() {
+        let board = sample();
+        let svg = render_svg(&board, &[], SIZE);
+        for p in 0..board.num_players() {
+            let tokens = board.tokens();
+            let mine: Vec<_> = tokens.iter().filter(|(_pos, t)| t.owner() == p).collect();
+            let fill = format!("fill=\"{}\"", rgb(mine[0].1.color()));
+            assert_eq!(count(&svg, &fill), mine.len(), "player {}", p);
+        }
+        // the runner and guard have outlines, the pawns are circles
+        assert_eq!(count(&svg, "<polygon"), 2);
+    }
+
+    #[test]
+    fn state_arcs_follow_the_token_states() {
+        let svg = render_svg(&sample(), &[], SIZE);
+        let ready = format!("stroke=\"{}\" stroke-width=\"8\"", rgb(color::BRIGHTGREEN));
+        assert_eq!(count(&svg, &ready), 2);
+        assert_eq!(count(&svg, "fill=\"none\""), 4);
+        // the Prep and Wait timers are partial arcs
+        assert_eq!(count(&svg, "<path d=\"M "), 2);
+        assert_eq!(
+            count(&svg, &format!("stroke=\"{}\"", rgb(color::YELLOW))),
+            1
+        );
+        assert_eq!(count(&svg, &format!("stroke=\"{}\"", rgb(color::RED))), 1);
+    }
+
+    #[test]
+    fn arrows_get_a_line_and_a_head() {
+        let board = sample();
+        assert!(!render_svg(&board, &[], SIZE).contains("marker-end"));
+        let arrow = Arrow {
+            from: (0, 0),
+            to: (1, 0),
+            color: color::RED,
+        };
+        let svg = render_svg(&board, &[arrow], SIZE);
+        assert_eq!(svg.matches("<marker id=\"head0\"").count(), 1);
+        assert_eq!(count(&svg, "marker-end=\"url(#head0)\""), 1);
+        // from the center of the first cell to the center of the second
+        assert!(svg.contains("x1=\"50.00\" y1=\"100.00\" x2=\"150.00\" y2=\"100.00\""));
+    }
+}
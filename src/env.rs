@@ -0,0 +1,377 @@
+//! Gym-style wrapper around the simulation for training agents without the piston loop.
+//!
+//! ```ignore
+//! let mut env = Env::new(2, EnvConfig::default())?;
+//! let mut obs = env.reset(7);
+//! loop {
+//!     let actions = [Some(0), None];
+//!     let (next, rewards, done) = env.step(&actions);
+//!     obs = next;
+//!     if done { break; }
+//! }
+//! ```
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::game_objects::player_constants;
+use crate::game_objects::{Board, Cell, MatchOptions, TokenClass, TokenId, TokenStates};
+use crate::replay::DEFAULT_TICK;
+
+/// Moves a token can be asked for, covering every token class: one cell orthogonally then
+/// diagonally, two cells orthogonally, then the knight's moves, each group clockwise from
+/// north. Which of them a token may actually make depends on its class; see `action_mask`.
+pub const DIRECTIONS: [(i32, i32); 20] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+    (0, -2),
+    (2, 0),
+    (0, 2),
+    (-2, 0),
+    (1, -2),
+    (2, -1),
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+];
+pub const ACTIONS_PER_TOKEN: u32 = DIRECTIONS.len() as u32;
+
+// Per-cell channels after the one-hot owner channels, as offsets from the first of them.
+const WAIT_CHANNEL: usize = 0;
+const PREP_CHANNEL: usize = 1;
+const READY_CHANNEL: usize = 2;
+const TIMER_CHANNEL: usize = 3;
+const WALL_CHANNEL: usize = 4;
+const HASTE_CHANNEL: usize = 5;
+const SAFE_CHANNEL: usize = 6;
+const RUNNER_CHANNEL: usize = 7;
+const JUMPER_CHANNEL: usize = 8;
+const GUARD_CHANNEL: usize = 9;
+const PROMOTED_CHANNEL: usize = 10;
+const STATE_CHANNELS: usize = PROMOTED_CHANNEL + 1;
+
+const WIN_REWARD: f32 = 10.0;
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    /// Simulated seconds per board update.
+    pub tick: f64,
+    /// Board updates per `step`, so agents need not act at 60 Hz.
+    pub ticks_per_step: u32,
+    /// Simulated seconds after which an episode ends with no winner.
+    pub max_seconds: f64,
+    /// How `reset` sets up the board; its seed is replaced by the one `reset` is given.
+    pub options: MatchOptions,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            tick: DEFAULT_TICK,
+            ticks_per_step: 6,
+            max_seconds: 600.0,
+            options: MatchOptions::default(),
+        }
+    }
+}
+
+/// The board as a `[channels, rows, cols]` tensor in row-major order. Channels are one per
+/// player for ownership (1.0 where that player's token stands), then 1.0 for tokens in Wait,
+/// Prep and Ready respectively, then how far through its current state each token is, 0..1,
+/// then 1.0 on wall, haste and safe cells respectively, then 1.0 for runners, jumpers and
+/// guards respectively (pawns have none), then 1.0 for promoted tokens.
+#[derive(Clone, Debug)]
+pub struct Observation {
+    pub channels: usize,
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f32>,
+}
+
+impl Observation {
+    pub fn at(&self, channel: usize, row: usize, col: usize) -> f32 {
+        self.data[(channel * self.rows + row) * self.cols + col]
+    }
+}
+
+pub struct Env {
+    config: EnvConfig,
+    num_players: i32,
+    board: Board,
+    rng: StdRng,
+    time: f64,
+    /// Set once the episode has ended; `step` does nothing more until the next `reset`.
+    done: bool,
+    /// Each player's tokens in id order as of `reset`; actions address tokens by this slot.
+    slots: Vec<Vec<TokenId>>,
+}
+
+impl Env {
+    /// An environment for `num_players`, all of whom the configured setup or map must seat:
+    /// only `Rotational`, or a map with spawn zones for them, seats more than two.
+    pub fn new(num_players: i32, config: EnvConfig) -> Result<Env, String> {
+        let max = player_constants::COLORS.len() as i32;
+        if !(2..=max).contains(&num_players) {
+            return Err(format!("an environment needs 2 to {} players", max));
+        }
+        let mut env = Env {
+            config,
+            num_players,
+            board: Board::new(num_players),
+            rng: StdRng::seed_from_u64(0),
+            time: 0.0,
+            done: false,
+            slots: Vec::new(),
+        };
+        env.reset(0);
+        if let Some(p) = env.slots.iter().position(|s| s.is_empty()) {
+            return Err(format!(
+                "the {} setup leaves player {} of {} without tokens",
+                env.config.options.setup.name(),
+                p + 1,
+                num_players
+            ));
+        }
+        Ok(env)
+    }
+
+    /// Starts a new episode. The seed lays out the board, if the configured `Setup` is a
+    /// random one, and decides the order simultaneous actions are applied in, which settles
+    /// races for the same cell.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.config.options.seed = seed;
+        self.board = self.config.options.board(self.num_players);
+        self.rng = StdRng::seed_from_u64(seed);
+        self.time = 0.0;
+        self.done = false;
+        self.slots = vec![Vec::new(); self.num_players as usize];
+        for (_pos, t) in self.board.tokens() {
+            if t.owner() >= 0 && t.owner() < self.num_players {
+                self.slots[t.owner() as usize].push(t.id());
+            }
+        }
+        self.observe()
+    }
+
+    /// Number of discrete actions for player `p`: `slot * ACTIONS_PER_TOKEN + direction`.
+    pub fn action_space(&self, p: i32) -> u32 {
+        self.slots.get(p as usize).map_or(0, |s| s.len() as u32) * ACTIONS_PER_TOKEN
+    }
+
+    /// The cells an action moves between, if its token is still alive.
+    pub fn decode(&self, p: i32, action: u32) -> Option<((i32, i32), (i32, i32))> {
+        let slot = (action / ACTIONS_PER_TOKEN) as usize;
+        let d = DIRECTIONS[(action % ACTIONS_PER_TOKEN) as usize];
+        let id = *self.slots.get(p as usize)?.get(slot)?;
+        let (from, _t) = self.board.find_token(id)?;
+        Some((from, (from.0 + d.0, from.1 + d.1)))
+    }
+
+    /// Which of `p`'s actions the rules would accept right now.
+    pub fn action_mask(&self, p: i32) -> Vec<bool> {
+        (0..self.action_space(p))
+            .map(|a| match self.decode(p, a) {
+                Some((from, to)) => self.board.is_legal(p, from, to),
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Applies one optional action per player, advances the clock, and returns the new
+    /// observation, each player's reward and whether the episode is over. Rewards are +1 per
+    /// capture and -1 per token lost, plus a bonus or penalty for winning or losing. Once the
+    /// episode is over, stepping again changes nothing and rewards nothing.
+    pub fn step(&mut self, actions: &[Option<u32>]) -> (Observation, Vec<f32>, bool) {
+        let mut rewards = vec![0.0; self.num_players as usize];
+        if self.done {
+            return (self.observe(), rewards, true);
+        }
+        let mut order: Vec<usize> = (0..actions.len().min(self.num_players as usize)).collect();
+        order.shuffle(&mut self.rng);
+        for p in order {
+            let (from, to) = match actions[p].and_then(|a| self.decode(p as i32, a)) {
+                Some(mv) => mv,
+                None => continue,
+            };
+            if let Some(victim) = self.board.check_and_move_token(p as i32, from, to) {
+                rewards[p] += 1.0;
+                if victim >= 0 && victim < self.num_players {
+                    rewards[victim as usize] -= 1.0;
+                }
+            }
+        }
+        for _ in 0..self.config.ticks_per_step {
+            self.board.update(self.config.tick);
+            self.time += self.config.tick;
+        }
+        self.board.drain_events();
+
+        let winner = self.board.winner();
+        if let Some(w) = winner {
+            for (p, reward) in rewards.iter_mut().enumerate() {
+                if p as i32 == w {
+                    *reward += WIN_REWARD;
+                } else {
+                    *reward -= WIN_REWARD;
+                }
+            }
+        }
+        self.done = winner.is_some()
+            || self.board.drawn().is_some()
+            || self.board.players_remaining() == 0
+            || self.time >= self.config.max_seconds;
+        (self.observe(), rewards, self.done)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn observe(&self) -> Observation {
+        let (rows, cols) = (self.board.rows as usize, self.board.cols as usize);
+        let channels = self.num_players as usize + STATE_CHANNELS;
+        let mut data = vec![0.0; channels * rows * cols];
+        let mut set = |channel: usize, pos: (i32, i32), value: f32| {
+            data[(channel * rows + pos.1 as usize) * cols + pos.0 as usize] = value;
+        };
+        let first_state = self.num_players as usize;
+        for (pos, t) in self.board.tokens() {
+            if t.owner() >= 0 && t.owner() < self.num_players {
+                set(t.owner() as usize, pos, 1.0);
+            }
+            let state = match t.state {
+                TokenStates::Wait => WAIT_CHANNEL,
+                TokenStates::Prep => PREP_CHANNEL,
+                TokenStates::Ready => READY_CHANNEL,
+                TokenStates::Dead => continue,
+            };
+            set(first_state + state, pos, 1.0);
+            let class = match t.class() {
+                TokenClass::Pawn => None,
+                TokenClass::Runner => Some(RUNNER_CHANNEL),
+                TokenClass::Jumper => Some(JUMPER_CHANNEL),
+                TokenClass::Guard => Some(GUARD_CHANNEL),
+            };
+            if let Some(class) = class {
+                set(first_state + class, pos, 1.0);
+            }
+            if t.promoted() {
+                set(first_state + PROMOTED_CHANNEL, pos, 1.0);
+            }
+            let total = t.time() + t.time_left();
+            if total > 0.0 {
+                set(first_state + TIMER_CHANNEL, pos, (t.time() / total) as f32);
+            }
+        }
+        for row in 0..self.board.rows {
+            for col in 0..self.board.cols {
+                let terrain = match self.board.cell((col, row)) {
+                    Cell::Floor => continue,
+                    Cell::Wall => WALL_CHANNEL,
+                    Cell::Haste => HASTE_CHANNEL,
+                    Cell::Safe => SAFE_CHANNEL,
+                };
+                set(first_state + terrain, (col, row), 1.0);
+            }
+        }
+        Observation {
+            channels,
+            rows,
+            cols,
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Position, Setup};
+
+    fn config(setup: Setup) -> EnvConfig {
+        let mut config = EnvConfig::default();
+        config.options.setup = setup;
+        config
+    }
+
+    #[test]
+    fn new_rejects_players_the_setup_cannot_seat() {
+        assert!(Env::new(1, EnvConfig::default()).is_err());
+        assert!(Env::new(3, config(Setup::Classic)).is_err());
+        assert!(Env::new(4, config(Setup::Mirrored)).is_err());
+        let env = Env::new(4, config(Setup::Rotational)).unwrap();
+        assert!((0..4).all(|p| env.action_space(p) > 0));
+    }
+
+    #[test]
+    fn step_after_the_end_rewards_nothing() {
+        let config = EnvConfig {
+            max_seconds: 0.5,
+            ..EnvConfig::default()
+        };
+        let mut env = Env::new(2, config).unwrap();
+        let mut steps = 0;
+        while !env.step(&[None, None]).2 {
+            steps += 1;
+            assert!(steps < 100, "episode never ended");
+        }
+        let before = env.observe().data;
+        for _ in 0..3 {
+            let (obs, rewards, done) = env.step(&[Some(0), Some(0)]);
+            assert!(done);
+            assert_eq!(rewards, vec![0.0, 0.0]);
+            assert_eq!(obs.data, before);
+        }
+        env.reset(1);
+        assert!(!env.step(&[None, None]).2);
+    }
+
+    #[test]
+    fn observe_puts_each_token_fact_in_its_channel() {
+        let mut env = Env::new(2, EnvConfig::default()).unwrap();
+        env.board = Position::parse("0rR+:2 1w:1/4\n. 0pG:5").unwrap().board;
+        let obs = env.observe();
+        let first_state = 2;
+        assert_eq!(
+            (obs.channels, obs.rows, obs.cols),
+            (2 + STATE_CHANNELS, 2, 2)
+        );
+        let on = |channel: usize, row: usize, col: usize| obs.at(channel, row, col) == 1.0;
+
+        // owners
+        assert!(on(0, 0, 0) && on(1, 0, 1) && on(0, 1, 1));
+        assert!(!on(1, 0, 0) && !on(0, 0, 1) && !on(0, 1, 0));
+        // states
+        assert!(on(first_state + READY_CHANNEL, 0, 0));
+        assert!(on(first_state + WAIT_CHANNEL, 0, 1));
+        assert!(on(first_state + PREP_CHANNEL, 1, 1));
+        assert!(!on(first_state + WAIT_CHANNEL, 0, 0) && !on(first_state + READY_CHANNEL, 1, 1));
+        // how far through its state each token is
+        let timer = |row, col| obs.at(first_state + TIMER_CHANNEL, row, col);
+        assert!((timer(0, 0) - 0.2).abs() < 1e-6);
+        assert!((timer(0, 1) - 0.25).abs() < 1e-6);
+        assert!((timer(1, 1) - 0.5).abs() < 1e-6);
+        assert_eq!(timer(1, 0), 0.0);
+        // classes and promotion
+        assert!(on(first_state + RUNNER_CHANNEL, 0, 0));
+        assert!(on(first_state + GUARD_CHANNEL, 1, 1));
+        assert!(on(first_state + PROMOTED_CHANNEL, 0, 0));
+        assert!(!on(first_state + PROMOTED_CHANNEL, 1, 1));
+        // an open board has no terrain
+        for channel in WALL_CHANNEL..=SAFE_CHANNEL {
+            for (row, col) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                assert_eq!(obs.at(first_state + channel, row, col), 0.0);
+            }
+        }
+    }
+}
@@ -1,45 +1,128 @@
-use piston_window::{clear, Context, Graphics};
+use piston_window::{clear, Context, G2d, G2dTextureContext};
 
-use crate::drawing::{color, screen};
-use crate::game_objects::{Board, HumanPlayer, InputTypes};
+use crate::drawing::{color, screen, Animator, Layout, RenderCache};
+use crate::game_objects::{
+    player_constants, Board, BoardEvent, Controller, HumanPlayer, InputTypes, MatchOptions,
+    TokenClass,
+};
+use crate::replay::{RecordedMove, Replay, DEFAULT_TICK};
 
-const SCREEN_WIDTH: i64 = screen::WIDTH;
-const SCREEN_HEIGHT: i64 = screen::HEIGHT;
-const BOARD_SIZE: i32 = screen::SIZE;
-
-const CELL_HEIGHT: f64 = (SCREEN_HEIGHT as f64 / BOARD_SIZE as f64) as f64;
-const CELL_WIDTH: f64 = (SCREEN_WIDTH as f64 / BOARD_SIZE as f64) as f64;
+const SCREEN_WIDTH: f64 = screen::WIDTH as f64;
+const SCREEN_HEIGHT: f64 = (screen::HEIGHT + screen::HUD_HEIGHT) as f64;
+const HUD_HEIGHT: f64 = screen::HUD_HEIGHT as f64;
 
 const NUM_PLAYERS: i32 = 2;
+const KILL_FEED_LEN: usize = 4;
+/// Seconds a promotion stays announced.
+const ANNOUNCE_SECONDS: f64 = 3.0;
 
 #[derive(Default)]
 struct Timers {
     current_time: f64,
+    /// The last time any token could have captured something.
+    last_threat: f64,
+}
+
+/// How a match ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Win { player: i32, reason: String },
+    Draw { reason: String },
+}
+
+pub struct KillEvent {
+    pub time: f64,
+    pub killer: i32,
+    pub victim: i32,
+}
+
+pub struct PromotionEvent {
+    pub time: f64,
+    pub player: i32,
+    pub class: TokenClass,
+}
+
+pub struct PlayerSummary {
+    pub name: String,
+    pub color: [f32; 4],
+    pub tokens: i32,
+    pub kills: i32,
 }
 
 pub struct Game {
     timers: Timers,
     board: Board,
+    layout: Layout,
+    animator: Animator,
+    render_cache: RenderCache,
     players: Vec<HumanPlayer>,
+    bots: Vec<Box<dyn Controller>>,
+    // the kill feed; the board keeps the counts the match is judged on
+    kills: Vec<KillEvent>,
+    promotions: Vec<PromotionEvent>,
+    replay: Replay,
+    show_threats: bool,
+    outcome: Option<Outcome>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
-        let board = Board::new(NUM_PLAYERS);
         let mut players = Vec::with_capacity(NUM_PLAYERS as usize);
 
         players.push(HumanPlayer::new(0, InputTypes::Mouse));
         players.push(HumanPlayer::new(1, InputTypes::Keyboard));
 
+        Game::with_players(players)
+    }
+    /// A match between the given seats, one per player number.
+    pub fn with_players(players: Vec<HumanPlayer>) -> Game {
+        Game::with_options(players, MatchOptions::default())
+    }
+    /// A match between the given seats, started the way `options` describes.
+    pub fn with_options(players: Vec<HumanPlayer>, options: MatchOptions) -> Game {
+        let num_players = (players.len() as i32).max(NUM_PLAYERS);
+        let board = options.board(num_players);
+        let mut replay = Replay::new(num_players, DEFAULT_TICK);
+        replay.options = options;
+
+        let layout = Layout::fit(
+            [SCREEN_WIDTH, SCREEN_HEIGHT],
+            HUD_HEIGHT,
+            board.cols,
+            board.rows,
+        );
+
         Game {
             timers: Timers::default(),
             board,
+            layout,
+            animator: Animator::new(),
+            render_cache: RenderCache::new(),
             players,
+            bots: Vec::new(),
+            kills: Vec::new(),
+            promotions: Vec::new(),
+            replay,
+            show_threats: false,
+            outcome: None,
         }
     }
+    /// Seats a bot, e.g. an `ExternalPlayer`, alongside the humans.
+    pub fn add_controller(&mut self, bot: Box<dyn Controller>) {
+        self.bots.push(bot);
+    }
     pub fn handle_mouse_click(&mut self, _b: piston_window::MouseButton, c: [f64; 2]) {
-        let cell_row: i32 = (c[0] / CELL_WIDTH) as i32;
-        let cell_col: i32 = (c[1] / CELL_HEIGHT) as i32;
+        // clicks on the HUD band or the letterbox margins do not select cells
+        let (cell_row, cell_col) = match self.layout.cell_at(c) {
+            Some(cell) => cell,
+            None => return,
+        };
         for human in self.players.iter_mut() {
             match human.input_type {
                 InputTypes::Mouse => human.handle_mouse_click((cell_row, cell_col)),
@@ -50,6 +133,10 @@ impl Game {
         //              c[0], c[1], cell_row, cell_col);
     }
     pub fn handle_key_press(&mut self, b: piston_window::Key) {
+        if b == piston_window::Key::T {
+            self.show_threats = !self.show_threats;
+            return;
+        }
         for human in self.players.iter_mut() {
             match human.input_type {
                 InputTypes::Mouse => {}
@@ -57,24 +144,317 @@ impl Game {
             }
         }
     }
-    pub fn render<G: Graphics>(&mut self, c: &Context, g: &mut G) {
+    /// Sends a key to one keyboard player only, for frontends where players share a keyboard
+    /// with separate key sets.
+    pub fn handle_key_press_for(&mut self, p: i32, b: piston_window::Key) {
+        for human in self.players.iter_mut() {
+            if human.player_num() == p {
+                human.handle_key_press(b);
+            }
+        }
+    }
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+    pub fn human(&self, p: i32) -> Option<&HumanPlayer> {
+        self.players.iter().find(|human| human.player_num() == p)
+    }
+    /// How the match ended, once it has.
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+    /// The player who won, once the match is over and was not drawn.
+    pub fn winner(&self) -> Option<i32> {
+        match self.outcome {
+            Some(Outcome::Win { player, .. }) => Some(player),
+            _ => None,
+        }
+    }
+    /// The end screen's headline once the match is over, e.g. "Player 1 wins: held the hill".
+    pub fn outcome_text(&self) -> Option<String> {
+        match self.outcome.as_ref()? {
+            Outcome::Win { player, reason } => {
+                Some(format!("{} wins: {}", self.player_name(*player), reason))
+            }
+            Outcome::Draw { reason } => Some(format!("Draw: {}", reason)),
+        }
+    }
+    /// Seconds left on the match clock, if the match has a time limit.
+    pub fn time_left(&self) -> Option<f64> {
+        let limit = self.replay.options.time_limit?;
+        Some((limit - self.timers.current_time).max(0.0))
+    }
+    /// Whether the match is over as of now: won by the rules, drawn when nobody has tokens
+    /// left, decided by tiebreak when the clock runs out, or drawn when no capture has been
+    /// possible for too long.
+    fn judge(&self) -> Option<Outcome> {
+        if let Some(player) = self.board.winner() {
+            let reason = self.board.rules().victory(&self.board, player);
+            return Some(Outcome::Win { player, reason });
+        }
+        if let Some(reason) = self.board.drawn() {
+            return Some(Outcome::Draw { reason });
+        }
+        // sudden death can take the last tokens of every side at once
+        if self.board.players_remaining() == 0 {
+            return Some(Outcome::Draw {
+                reason: String::from("no tokens left"),
+            });
+        }
+        let options = &self.replay.options;
+        let now = self.timers.current_time;
+        if matches!(options.time_limit, Some(limit) if now >= limit) {
+            return Some(self.tiebreak());
+        }
+        match options.draw_after {
+            Some(after) if now - self.timers.last_threat >= after => Some(Outcome::Draw {
+                reason: format!("no capture possible for {:.0}s", after),
+            }),
+            _ => None,
+        }
+    }
+    /// Decides a match that ran out of time: most surviving tokens, then most kills. Players
+    /// level on both draw.
+    fn tiebreak(&self) -> Outcome {
+        let standing = |p: i32| (self.board.tokens_remaining(p), self.board.kills(p));
+        let best = (0..self.board.num_players()).map(standing).max();
+        let mut leaders = (0..self.board.num_players()).filter(|p| Some(standing(*p)) == best);
+        let (player, tokens_only) = match (leaders.next(), leaders.next()) {
+            (Some(p), None) => {
+                let tokens = standing(p).0;
+                let runner_up = (0..self.board.num_players())
+                    .filter(|q| *q != p)
+                    .map(|q| standing(q).0)
+                    .max()
+                    .unwrap_or(0);
+                (p, runner_up < tokens)
+            }
+            _ => {
+                return Outcome::Draw {
+                    reason: String::from("time up, level on tokens and kills"),
+                }
+            }
+        };
+        let reason = if tokens_only {
+            "time up, most tokens left"
+        } else {
+            "time up, most kills"
+        };
+        Outcome::Win {
+            player,
+            reason: String::from(reason),
+        }
+    }
+    /// How the players stand towards the game mode's goal, if it has one.
+    pub fn mode_status(&self) -> Option<String> {
+        self.board.rules().status(&self.board)
+    }
+    /// How long until sudden death next closes in the arena, while it has rings left to take.
+    pub fn collapse_status(&self) -> Option<String> {
+        let (_ring, left) = self.board.next_collapse()?;
+        Some(format!("arena shrinks in {:.0}s", left.max(0.0).ceil()))
+    }
+    /// Refits the board to a new window size, given in points rather than pixels.
+    pub fn resize(&mut self, window_size: [f64; 2]) {
+        self.layout = Layout::fit(window_size, HUD_HEIGHT, self.board.cols, self.board.rows);
+    }
+    /// The match so far, for saving and later playback.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+    pub fn elapsed(&self) -> f64 {
+        self.timers.current_time
+    }
+    /// Every seat, human or not, as (player number, name).
+    fn seats(&self) -> impl Iterator<Item = (i32, String)> + '_ {
+        let humans = self.players.iter().map(|h| (h.player_num(), h.name()));
+        let bots = self.bots.iter().map(|b| (b.player_num(), b.name()));
+        humans.chain(bots)
+    }
+    pub fn player_summaries(&self) -> Vec<PlayerSummary> {
+        let mut seats: Vec<_> = self.seats().collect();
+        seats.sort_by_key(|(p, _name)| *p);
+        seats
+            .into_iter()
+            .map(|(p, name)| PlayerSummary {
+                name,
+                color: player_constants::COLORS[p as usize],
+                tokens: self.board.tokens_remaining(p),
+                kills: self.board.kills(p),
+            })
+            .collect()
+    }
+    pub fn player_name(&self, p: i32) -> String {
+        match self.seats().find(|(seat, _name)| *seat == p) {
+            Some((_seat, name)) => name,
+            None => format!("Player {}", p + 1),
+        }
+    }
+    /// The most recent kills, newest first.
+    pub fn kill_feed(&self) -> impl Iterator<Item = &KillEvent> {
+        self.kills.iter().rev().take(KILL_FEED_LEN)
+    }
+    /// Headline for the latest promotion while it is news, e.g. "Player 1's runner is promoted".
+    pub fn announcement(&self) -> Option<String> {
+        let e = self
+            .promotions
+            .last()
+            .filter(|e| self.timers.current_time - e.time < ANNOUNCE_SECONDS)?;
+        Some(format!(
+            "{}'s {} is promoted",
+            self.player_name(e.player),
+            e.class.name()
+        ))
+    }
+    pub fn render(&mut self, c: &Context, g: &mut G2d<'_>, tc: &mut G2dTextureContext) {
         // Clear everything
         clear(color::BLACK, g);
-        self.board.draw(&c, g);
+        self.render_cache
+            .draw_background(c, g, tc, &self.board, &self.layout);
+        self.board.draw_zones(c, g, &self.layout);
+        self.board.draw_collapse(c, g, &self.layout);
+        if self.show_threats {
+            // with no humans seated, show every side's view
+            let mut viewers: Vec<i32> = self.players.iter().map(|h| h.player_num()).collect();
+            if viewers.is_empty() {
+                viewers = (0..self.board.num_players()).collect();
+            }
+            self.board.draw_threats(c, g, &self.layout, &viewers);
+        }
+        self.animator.tick();
+        for (pos, t) in self.board.tokens() {
+            let rect = self.animator.token_rect(&self.layout, t.id(), pos);
+            t.draw_state_arc_in(c, g, rect);
+            self.render_cache
+                .draw_body(c, g, tc, &self.layout, t.class(), t.color(), rect);
+            t.draw_promotion_mark_in(c, g, rect);
+        }
+        self.animator.draw_effects(c, g, &self.layout);
         for human in self.players.iter() {
-            human.draw_selection(c, g);
+            human.draw_selection(c, g, &self.layout);
         }
     }
     pub fn update(&mut self, dt: f64) {
-        self.timers.current_time += dt;
-        let player_count = self.board.players_remaining();
-        if player_count == 1 {
-            println!("VICTORY");
+        if self.outcome.is_some() {
             return;
         }
+        self.timers.current_time += dt;
+        // piston updates on a fixed step, so one tick length describes the whole match
+        self.replay.tick_seconds = dt;
+        let mut moves = Vec::new();
         for human in self.players.iter_mut() {
-            human.update(&mut self.board);
+            if let Some(m) = human.take_move() {
+                moves.push((human.player_num(), m));
+            }
+        }
+        for bot in self.bots.iter_mut() {
+            bot.observe(&self.board, self.timers.current_time);
+            if let Some(m) = bot.take_move() {
+                moves.push((bot.player_num(), m));
+            }
+        }
+        for (p, (from, to)) in moves {
+            self.replay.moves.push(RecordedMove {
+                tick: self.replay.ticks,
+                player: p,
+                from,
+                to,
+            });
+            if let Some(victim) = self.board.check_and_move_token(p, from, to) {
+                self.kills.push(KillEvent {
+                    time: self.timers.current_time,
+                    killer: p,
+                    victim,
+                });
+            }
         }
         self.board.update(dt);
+        self.replay.ticks += 1;
+        for event in self.board.drain_events() {
+            if let BoardEvent::Promoted { id, owner, .. } = event {
+                if let Some((_pos, t)) = self.board.find_token(id) {
+                    self.promotions.push(PromotionEvent {
+                        time: self.timers.current_time,
+                        player: owner,
+                        class: t.class(),
+                    });
+                }
+            }
+            self.animator.push(event);
+        }
+        // a headless game never renders, so finished effects are dropped here as well
+        self.animator.tick();
+        if self.board.capture_possible() {
+            self.timers.last_threat = self.timers.current_time;
+        }
+        self.outcome = self.judge();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    fn game_on(position: &str) -> Game {
+        let mut game = Game::with_options(Vec::new(), MatchOptions::default());
+        game.board = Position::parse(position).unwrap().board;
+        game
+    }
+
+    fn capture(game: &mut Game, p: i32, from: (i32, i32), to: (i32, i32)) {
+        assert!(game.board.check_and_move_token(p, from, to).is_some());
+    }
+
+    #[test]
+    fn tiebreak_goes_to_tokens_then_kills() {
+        let game = game_on("0r . 1r\n0r . .");
+        assert_eq!(
+            game.tiebreak(),
+            Outcome::Win {
+                player: 0,
+                reason: String::from("time up, most tokens left")
+            }
+        );
+
+        // tokens beat kills
+        let mut game = game_on("0r . . 0r\n. 1r . .\n0r . . .");
+        capture(&mut game, 1, (1, 1), (0, 0));
+        assert_eq!(game.tiebreak(), game_on("0r . 1r\n0r . .").tiebreak());
+
+        let mut game = game_on("0r . . 0r\n. 1r . .");
+        capture(&mut game, 1, (1, 1), (0, 0));
+        assert_eq!(
+            game.tiebreak(),
+            Outcome::Win {
+                player: 1,
+                reason: String::from("time up, most kills")
+            }
+        );
+
+        let mut game = game_on("0r . . 1r\n. 1r 0r .");
+        capture(&mut game, 0, (2, 1), (3, 0));
+        capture(&mut game, 1, (1, 1), (0, 0));
+        assert_eq!(
+            game.tiebreak(),
+            Outcome::Draw {
+                reason: String::from("time up, level on tokens and kills")
+            }
+        );
+    }
+
+    #[test]
+    fn time_limit_ends_the_match_with_the_tiebreak() {
+        let options = MatchOptions {
+            time_limit: Some(1.0),
+            ..MatchOptions::default()
+        };
+        let mut game = Game::with_options(Vec::new(), options);
+        while game.outcome().is_none() {
+            assert!(game.elapsed() < 2.0, "time limit never reached");
+            game.update(0.1);
+        }
+        assert!(game.elapsed() >= 1.0);
+        assert_eq!(game.outcome(), Some(&game.tiebreak()));
     }
 }
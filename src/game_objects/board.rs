@@ -1,25 +1,66 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
 use piston_window::{Context, Graphics, Line, Rectangle};
 
 use crate::drawing::color;
-use crate::drawing::screen;
+use crate::drawing::Layout;
 
-use crate::game_objects::player_constants;
+use crate::game_objects::BoardEvent;
+use crate::game_objects::Cell;
+use crate::game_objects::Edge;
+use crate::game_objects::Map;
+use crate::game_objects::Setup;
+use crate::game_objects::SuddenDeath;
 use crate::game_objects::Token;
+use crate::game_objects::TokenId;
 use crate::game_objects::TokenStates;
+use crate::game_objects::{ClassicRules, Rules};
+
+const THREAT_ALPHA: f32 = 0.15;
+const THREAT_READY_ALPHA: f32 = 0.4;
+
+/// Undrained events kept before the oldest are dropped, for boards whose owner never reads
+/// them, such as search copies.
+const MAX_EVENTS: usize = 4096;
 
-const SCREEN_WIDTH: i64 = screen::WIDTH;
-const SCREEN_HEIGHT: i64 = screen::HEIGHT;
-const BOARD_SIZE: i32 = screen::SIZE;
-const SQ_BOARD_SIZE: i32 = BOARD_SIZE * BOARD_SIZE;
+/// How much faster timers run for a token standing on a Haste cell.
+const HASTE_RATE: f64 = 2.0;
 
-const MAX_TOKENS: i32 = player_constants::MAX_TOKENS;
-const TOKEN_OFFSET: i32 = (BOARD_SIZE - MAX_TOKENS) / 2;
+/// Tint of a ring about to collapse, faint when its warning starts and strongest just before.
+const COLLAPSE_ALPHA_MIN: f32 = 0.15;
+const COLLAPSE_ALPHA_MAX: f32 = 0.6;
+
+/// Queues an event, dropping the oldest half once `MAX_EVENTS` are waiting.
+fn push_event(events: &mut Vec<BoardEvent>, event: BoardEvent) {
+    if events.len() >= MAX_EVENTS {
+        events.drain(..MAX_EVENTS / 2);
+    }
+    events.push(event);
+}
 
+#[derive(Clone)]
 pub struct Board {
     pub rows: i32,
     pub cols: i32,
     surface: Vec<Option<Token>>,
+    terrain: Vec<Cell>,
     num_players: i32,
+    events: Vec<BoardEvent>,
+    rules: Arc<dyn Rules>,
+    /// Seconds the board has been updated for.
+    elapsed: f64,
+    /// Per-player progress kept by the rules, e.g. seconds on the hill.
+    scores: Vec<f64>,
+    /// Tokens each player has captured by moving, indexed by player.
+    kills: Vec<i32>,
+    sudden_death: Option<SuddenDeath>,
+    /// Rings sudden death has turned to wall so far.
+    collapsed: i32,
+    /// The edge each player's tokens are promoted on, indexed by player; empty when the
+    /// match has no promotion.
+    promotion: Vec<Option<Edge>>,
 }
 
 impl Board {
@@ -31,145 +72,485 @@ impl Board {
         ((p1.0 - p2.0).abs() == 1) && ((p1.1 - p2.1).abs() == 1)
     }
 
-    pub fn new(np: i32) -> Board {
-        let mut out = Vec::with_capacity((np * SQ_BOARD_SIZE) as usize);
-        for (_i, _inum) in (0..np).enumerate() {
-            for (_j, _jnum) in (0..BOARD_SIZE).enumerate() {
-                for (_k, _knum) in (0..BOARD_SIZE).enumerate() {
-                    out.push(Option::None);
-                }
-            }
-        }
-        // TODO: more generic token placement? owned by player?
-        for (_j, jnum) in (0..MAX_TOKENS).enumerate() {
-            out[((jnum + TOKEN_OFFSET) * BOARD_SIZE) as usize] = Some(Token::new(color::GREEN));
-            out[(SQ_BOARD_SIZE + (jnum + TOKEN_OFFSET) * BOARD_SIZE + (BOARD_SIZE - 1)) as usize] =
-                Some(Token::new(color::ORANGE));
+    /// A board with no tokens on it.
+    pub fn empty(cols: i32, rows: i32, np: i32) -> Board {
+        Board::on_map(&Map::open(cols, rows), np)
+    }
+
+    /// A board with the map's terrain and no tokens on it.
+    pub fn on_map(map: &Map, np: i32) -> Board {
+        let mut out = Vec::with_capacity((map.cols * map.rows) as usize);
+        for _cell in 0..map.cols * map.rows {
+            out.push(Option::None);
         }
         Board {
-            rows: BOARD_SIZE,
-            cols: BOARD_SIZE,
+            rows: map.rows,
+            cols: map.cols,
             surface: out,
+            terrain: map.cells().to_vec(),
             num_players: np,
+            events: Vec::new(),
+            rules: Arc::new(ClassicRules),
+            elapsed: 0.0,
+            scores: vec![0.0; np.max(0) as usize],
+            kills: vec![0; np.max(0) as usize],
+            sudden_death: None,
+            collapsed: 0,
+            promotion: Vec::new(),
+        }
+    }
+
+    /// The rules moves and victory are judged by; classic unless `set_rules` says otherwise.
+    pub fn rules(&self) -> &dyn Rules {
+        self.rules.as_ref()
+    }
+
+    pub fn set_rules(&mut self, rules: Arc<dyn Rules>) {
+        self.rules = rules;
+    }
+
+    pub fn new(np: i32) -> Board {
+        Board::generate(np, Setup::Classic, 0)
+    }
+
+    /// Puts a token on an empty cell, replacing nothing; returns false if the cell is taken
+    /// or is a wall.
+    pub fn place_token(&mut self, loc: (i32, i32), t: Token) -> bool {
+        if !self.in_bounds(loc) || self.token_at(loc).is_some() || self.cell(loc) == Cell::Wall {
+            return false;
         }
+        let ind = self.surface_index(loc);
+        self.surface[ind] = Some(t);
+        true
     }
 
-    pub fn check_and_move_token(&mut self, p: i32, from: (i32, i32), to: (i32, i32)) {
+    /// Moves the token if the move is legal, returning the player whose token was captured.
+    pub fn check_and_move_token(
+        &mut self,
+        p: i32,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) -> Option<i32> {
+        let mut victim = None;
         // check that the move is legal
         if self.is_legal(p, from, to) {
             // move the token, and change states as needed
-            if Board::is_diagonal(from, to) {
-                self.kill_token_at(to);
+            if let Some(at) = self.rules.captured_cell(self, p, from, to) {
+                victim = self.kill_token_at(at);
+                if let (Some(_), Some(kills)) = (victim, self.kills.get_mut(p as usize)) {
+                    *kills += 1;
+                }
             }
-            self.move_token(p, from, to);
+            assert!(
+                self.token_at(to).is_none(),
+                "{} rules moved onto {:?} without clearing it",
+                self.rules.name(),
+                to
+            );
+            self.move_token(from, to);
+            self.promote_if_arrived(to);
+            let rules = Arc::clone(&self.rules);
+            rules.on_move(self, p, to, victim);
         }
+        victim
     }
 
-    fn move_token(&mut self, p: i32, from: (i32, i32), to: (i32, i32)) {
-        let from_ind = self.surface_index(p, from);
-        let to_ind = self.surface_index(p, to);
-        match self.surface[from_ind].as_mut() {
+    fn move_token(&mut self, from: (i32, i32), to: (i32, i32)) {
+        let from_ind = self.surface_index(from);
+        let to_ind = self.surface_index(to);
+        let id = match self.surface[from_ind].as_mut() {
             Some(t) => {
-                t.state = TokenStates::Wait;
-                t.set_wait_time(4.0);
+                self.rules.after_move(t);
+                t.id()
             }
             None => return,
-        }
+        };
         self.surface.swap(from_ind, to_ind);
+        push_event(&mut self.events, BoardEvent::Moved { id, from, to });
         // println!("Swapped! From: ({}, {}) To: ({}, {})", from.0, from.1, to.0, to.1);
     }
 
-    fn is_legal(&mut self, p: i32, from: (i32, i32), to: (i32, i32)) -> bool {
-        match self.surface[self.surface_index(p, from)].as_ref() {
-            Some(t) => {
-                if t.state != TokenStates::Ready {
-                    return false;
+    /// Whether `p` may move the token at `from` to `to` right now, by the board's rules.
+    pub fn is_legal(&self, p: i32, from: (i32, i32), to: (i32, i32)) -> bool {
+        self.rules.is_legal(self, p, from, to)
+    }
+
+    /// True if every cell strictly between two cells on a straight line is empty and not a
+    /// wall; moves off a straight line pass over nothing.
+    pub fn path_clear(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let len = dx.abs().max(dy.abs());
+        if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+            return true;
+        }
+        (1..len).all(|i| {
+            let via = (from.0 + dx / len * i, from.1 + dy / len * i);
+            self.token_at(via).is_none() && self.cell(via) != Cell::Wall
+        })
+    }
+
+    /// Every cell the token at `from` could legally move to right now, captures first.
+    pub fn legal_targets(&self, p: i32, from: (i32, i32)) -> Vec<(i32, i32)> {
+        self.rules.targets(self, p, from)
+    }
+
+    /// Every move the rules allow `p` right now as `(from, to)`, captures first.
+    pub fn legal_moves(&self, p: i32) -> Vec<((i32, i32), (i32, i32))> {
+        let mut captures = Vec::new();
+        let mut steps = Vec::new();
+        for (from, t) in self.tokens() {
+            if t.owner() != p || t.state != TokenStates::Ready {
+                continue;
+            }
+            for to in self.legal_targets(p, from) {
+                if self.token_at(to).is_some() {
+                    captures.push((from, to));
+                } else {
+                    steps.push((from, to));
                 }
             }
-            None => return false,
-        }
-        // check destination in all player layers for occupancy
-        let mut occupied: bool = false;
-        let mut occupying_player: i32 = -1;
-        for i in 0..self.num_players {
-            match self.surface[self.surface_index(i, to)].as_ref() {
-                Some(_t) => {
-                    occupied = true;
-                    occupying_player = i;
+        }
+        captures.extend(steps);
+        captures
+    }
+
+    /// True if any Ready token could capture something right now.
+    pub fn capture_possible(&self) -> bool {
+        self.tokens().iter().any(|(pos, t)| {
+            t.state == TokenStates::Ready
+                && self
+                    .legal_targets(t.owner(), *pos)
+                    .iter()
+                    .any(|to| self.token_at(*to).is_some())
+        })
+    }
+
+    /// The player who has won by the board's rules, if the match is decided.
+    pub fn winner(&self) -> Option<i32> {
+        self.rules.winner(self)
+    }
+
+    /// Why the board's rules call the match a draw, if they do.
+    pub fn drawn(&self) -> Option<String> {
+        self.rules.drawn(self)
+    }
+
+    /// Seconds of play so far, as counted by `update`.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Player `p`'s progress towards the rules' goal; what it measures is up to the rules.
+    pub fn score(&self, p: i32) -> f64 {
+        self.scores.get(p as usize).copied().unwrap_or(0.0)
+    }
+
+    /// Tokens `p` has captured; those lost to sudden death count for nobody.
+    pub fn kills(&self, p: i32) -> i32 {
+        self.kills.get(p as usize).copied().unwrap_or(0)
+    }
+
+    pub fn sudden_death(&self) -> Option<SuddenDeath> {
+        self.sudden_death
+    }
+
+    /// Starts closing in the board's outer rings once the match has run long enough; `None`
+    /// leaves the board as it is.
+    pub fn set_sudden_death(&mut self, sudden_death: Option<SuddenDeath>) {
+        self.sudden_death = sudden_death;
+    }
+
+    /// Turns promotion on or off. With it on, a token reaching the edge opposite where its
+    /// player's tokens stand now is promoted, so call this once the tokens are placed.
+    pub fn set_promotion(&mut self, enabled: bool) {
+        self.promotion = if enabled {
+            Edge::far_edges(self)
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Whether tokens reaching the far edge are promoted this match.
+    pub fn promotion(&self) -> bool {
+        !self.promotion.is_empty()
+    }
+
+    /// Promotes the token at `loc` if it stands on its owner's promotion edge and has not
+    /// been promoted yet.
+    fn promote_if_arrived(&mut self, loc: (i32, i32)) {
+        let owner = match self.token_at(loc) {
+            Some(t) if !t.promoted() => t.owner(),
+            _ => return,
+        };
+        let edge = match self.promotion.get(owner as usize).copied().flatten() {
+            Some(edge) => edge,
+            None => return,
+        };
+        if edge.progress(self, loc) < 1.0 {
+            return;
+        }
+        let ind = self.surface_index(loc);
+        if let Some(t) = self.surface[ind].as_mut() {
+            t.set_promoted(true);
+            push_event(
+                &mut self.events,
+                BoardEvent::Promoted {
+                    id: t.id(),
+                    at: loc,
+                    owner,
+                },
+            );
+        }
+    }
+
+    /// How many rings in from the edge a cell is, 0 for the outermost.
+    pub fn ring(&self, loc: (i32, i32)) -> i32 {
+        loc.0
+            .min(loc.1)
+            .min(self.cols - 1 - loc.0)
+            .min(self.rows - 1 - loc.1)
+    }
+
+    /// Rings sudden death has closed so far; the open arena starts this many cells in from
+    /// each edge.
+    pub fn collapsed_rings(&self) -> i32 {
+        self.collapsed
+    }
+
+    /// The next ring to collapse and the seconds until it does, while sudden death has rings
+    /// left to take.
+    pub fn next_collapse(&self) -> Option<(i32, f64)> {
+        let sudden_death = self.sudden_death?;
+        // the middle two rows or columns never close
+        if self.cols.min(self.rows) - 2 * (self.collapsed + 1) < 2 {
+            return None;
+        }
+        let at = sudden_death.collapse_time(self.collapsed);
+        Some((self.collapsed, at - self.elapsed))
+    }
+
+    /// The next ring to collapse and the seconds left, once its warning has started.
+    pub fn collapse_warning(&self) -> Option<(i32, f64)> {
+        let warning = self.sudden_death?.warning;
+        self.next_collapse()
+            .filter(|(_ring, left)| *left <= warning)
+    }
+
+    /// Turns every cell of a ring to wall, killing whatever stands there.
+    fn collapse_ring(&mut self, ring: i32) {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if self.ring((col, row)) == ring {
+                    let ind = self.surface_index((col, row));
+                    self.terrain[ind] = Cell::Wall;
+                    self.kill_token_at((col, row));
                 }
-                None => continue,
             }
         }
-        // RULES :
-        // regular move
-        (Board::manhattan(from, to) == 1   // horizontal or vertical one space
-        &&                                 // and
-        !occupied)                         // empty
-        ||                                 // or
-                        // kill move
-        occupied                           // destination is occupied
-        &&                                 // and
-        occupying_player != p              // enemy player occupies the space
-        &&                                 // and
-        Board::manhattan(from, to) == 2    // exactly 2 distance
-        &&                                 // and
-        Board::is_diagonal(from, to) // diagonal direction
-    }
-
-    fn kill_token_at(&mut self, loc: (i32, i32)) {
-        for (_i, inum) in (0..self.num_players).enumerate() {
-            let loc_ind = self.surface_index(inum, loc);
-            match self.surface[loc_ind].as_mut() {
-                Some(t) => t.state = TokenStates::Dead,
-                None => continue,
+        self.collapsed += 1;
+    }
+
+    pub fn add_score(&mut self, p: i32, amount: f64) {
+        if let Some(score) = self.scores.get_mut(p as usize) {
+            *score += amount;
+        }
+    }
+
+    fn kill_token_at(&mut self, loc: (i32, i32)) -> Option<i32> {
+        let loc_ind = self.surface_index(loc);
+        let victim = match self.surface[loc_ind].as_mut() {
+            Some(t) => {
+                t.state = TokenStates::Dead;
+                push_event(
+                    &mut self.events,
+                    BoardEvent::Killed {
+                        id: t.id(),
+                        at: loc,
+                        color: t.color(),
+                    },
+                );
+                t.owner()
             }
-            self.surface[loc_ind] = None;
+            None => return None,
+        };
+        self.surface[loc_ind] = None;
+        Some(victim)
+    }
+
+    fn surface_index(&self, loc: (i32, i32)) -> usize {
+        (loc.1 * self.cols + loc.0) as usize
+    }
+
+    fn cell_of(&self, ind: usize) -> (i32, i32) {
+        (ind as i32 % self.cols, ind as i32 / self.cols)
+    }
+
+    /// The terrain under a cell; outside the board counts as wall.
+    pub fn cell(&self, loc: (i32, i32)) -> Cell {
+        if !self.in_bounds(loc) {
+            return Cell::Wall;
+        }
+        self.terrain[self.surface_index(loc)]
+    }
+
+    /// Changes whenever the terrain does, so cached drawings of it know to redraw.
+    pub fn terrain_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.terrain.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn num_players(&self) -> i32 {
+        self.num_players
+    }
+
+    /// The token standing on a cell, if any.
+    pub fn token_at(&self, loc: (i32, i32)) -> Option<&Token> {
+        if !self.in_bounds(loc) {
+            return None;
+        }
+        self.surface[self.surface_index(loc)].as_ref()
+    }
+
+    /// Hands a token over to another player, e.g. for conversions or neutral pieces.
+    pub fn set_owner(&mut self, loc: (i32, i32), p: i32) {
+        if !self.in_bounds(loc) {
+            return;
+        }
+        let ind = self.surface_index(loc);
+        if let Some(t) = self.surface[ind].as_mut() {
+            t.set_owner(p);
+        }
+    }
+
+    /// Sets whose flag the token on a cell carries, for capture-the-flag.
+    pub fn set_flag(&mut self, loc: (i32, i32), flag: Option<i32>) {
+        if !self.in_bounds(loc) {
+            return;
+        }
+        let ind = self.surface_index(loc);
+        if let Some(t) = self.surface[ind].as_mut() {
+            t.set_flag(flag);
         }
     }
 
-    fn surface_index(&self, p: i32, loc: (i32, i32)) -> usize {
-        (p * SQ_BOARD_SIZE + loc.1 * BOARD_SIZE + loc.0) as usize
+    /// Finds a living token by id, along with the cell it stands on.
+    pub fn find_token(&self, id: TokenId) -> Option<((i32, i32), &Token)> {
+        self.surface
+            .iter()
+            .enumerate()
+            .find_map(|(i, token)| match token {
+                Some(t) if t.id() == id => Some((self.cell_of(i), t)),
+                _ => None,
+            })
+    }
+
+    /// All living tokens with their cells, in id order.
+    pub fn tokens(&self) -> Vec<((i32, i32), &Token)> {
+        let mut out: Vec<_> = self
+            .surface
+            .iter()
+            .enumerate()
+            .filter_map(|(i, token)| token.as_ref().map(|t| (self.cell_of(i), t)))
+            .collect();
+        out.sort_by_key(|(_pos, t)| t.id().0);
+        out
+    }
+
+    pub fn tokens_remaining(&self, p: i32) -> i32 {
+        self.surface
+            .iter()
+            .filter(|t| matches!(t, Some(t) if t.owner() == p))
+            .count() as i32
     }
 
+    /// Counts players that still have tokens; neutral tokens (negative owner) belong to nobody.
     pub fn players_remaining(&self) -> i32 {
-        let mut player_count: i32 = 0;
-        for inum in 0..self.num_players {
-            for jnum in (inum * SQ_BOARD_SIZE)..((inum + 1) * SQ_BOARD_SIZE) {
-                if let Some(_t) = self.surface[jnum as usize].as_ref() {
-                    player_count += 1;
-                    break;
-                }
+        let mut alive = vec![false; self.num_players as usize];
+        for t in self.surface.iter().flatten() {
+            if t.owner() >= 0 && t.owner() < self.num_players {
+                alive[t.owner() as usize] = true;
             }
         }
-        player_count
+        alive.iter().filter(|a| **a).count() as i32
     }
 
     pub fn update(&mut self, dt: f64) {
-        for token in self.surface.iter_mut() {
+        let cols = self.cols;
+        for (i, token) in self.surface.iter_mut().enumerate() {
             if let Some(t) = token.as_mut() {
-                t.update(dt);
+                let was_ready = t.state == TokenStates::Ready;
+                if self.terrain[i] == Cell::Haste {
+                    t.update(dt * HASTE_RATE);
+                } else {
+                    t.update(dt);
+                }
+                if !was_ready && t.state == TokenStates::Ready {
+                    push_event(
+                        &mut self.events,
+                        BoardEvent::Ready {
+                            id: t.id(),
+                            at: (i as i32 % cols, i as i32 / cols),
+                        },
+                    );
+                }
+            }
+        }
+        self.elapsed += dt;
+        while let Some((ring, left)) = self.next_collapse() {
+            if left > 0.0 {
+                break;
             }
+            self.collapse_ring(ring);
+        }
+        let rules = Arc::clone(&self.rules);
+        rules.on_tick(self, dt);
+    }
+
+    /// Takes the moves, captures and Ready transitions since the last call, oldest first. A
+    /// board nobody drains keeps only its few thousand most recent events.
+    pub fn drain_events(&mut self) -> Vec<BoardEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn in_bounds(&self, loc: (i32, i32)) -> bool {
+        loc.0 >= 0 && loc.0 < self.cols && loc.1 >= 0 && loc.1 < self.rows
+    }
+
+    /// Cells a token at `loc` capturing at `captures` would threaten, whatever stands there.
+    fn capture_cells(
+        &self,
+        loc: (i32, i32),
+        captures: &'static [(i32, i32)],
+    ) -> impl Iterator<Item = (i32, i32)> + '_ {
+        captures
+            .iter()
+            .map(move |d| (loc.0 + d.0, loc.1 + d.1))
+            .filter(move |n| self.in_bounds(*n))
+    }
+
+    /// Whether a cell gets the colored square of the checkerboard.
+    pub fn is_shaded(&self, loc: (i32, i32)) -> bool {
+        (loc.0 + loc.1) % 2 == 0
+    }
+
+    /// The fill for a cell's terrain, or `None` to leave the background showing.
+    pub fn cell_color(&self, loc: (i32, i32)) -> Option<[f32; 4]> {
+        match self.cell(loc) {
+            Cell::Floor if self.is_shaded(loc) => Some(color::VIOLET),
+            Cell::Floor => None,
+            Cell::Wall => Some(color::DARKGREY),
+            Cell::Haste => Some(color::GOLD),
+            Cell::Safe => Some(color::TEAL),
         }
     }
 
-    pub fn draw<G: Graphics>(&self, c: &Context, g: &mut G) {
-        let box_width = SCREEN_WIDTH as f64 / self.cols as f64;
-        let box_height = SCREEN_HEIGHT as f64 / self.rows as f64;
-        let mut colored_row = true;
-        let mut colored_col = false;
+    pub fn draw_cells<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
         for row in 0..self.rows {
-            colored_row = !colored_row;
-            if colored_row {
-                colored_row = !colored_row;
-                colored_col = !colored_col;
-            }
             for col in 0..self.cols {
-                colored_col = !colored_col;
-                if colored_col {
-                    let xpos = col as f64 * SCREEN_WIDTH as f64 / self.cols as f64;
-                    let ypos = row as f64 * SCREEN_HEIGHT as f64 / self.rows as f64;
-                    Rectangle::new(color::VIOLET).draw(
-                        [xpos, ypos, box_width, box_height],
+                if let Some(fill) = self.cell_color((col, row)) {
+                    Rectangle::new(fill).draw(
+                        layout.cell_rect((col, row)),
                         &c.draw_state,
                         c.transform,
                         g,
@@ -177,32 +558,313 @@ impl Board {
                 }
             }
         }
+        let board = layout.board_rect();
         for row in 1..self.rows {
-            let ypos = row as f64 * SCREEN_HEIGHT as f64 / self.rows as f64;
+            let ypos = board[1] + row as f64 * layout.cell;
             Line::new(color::LIGHTGREY, 1.0).draw(
-                [0.0, ypos, SCREEN_WIDTH as f64, ypos],
+                [board[0], ypos, board[0] + board[2], ypos],
                 &c.draw_state,
                 c.transform,
                 g,
             );
         }
         for col in 1..self.cols {
-            let xpos = col as f64 * SCREEN_WIDTH as f64 / self.cols as f64;
+            let xpos = board[0] + col as f64 * layout.cell;
             Line::new(color::LIGHTGREY, 1.0).draw(
-                [xpos, 0.0, xpos, SCREEN_HEIGHT as f64],
+                [xpos, board[1], xpos, board[1] + board[3]],
                 &c.draw_state,
                 c.transform,
                 g,
             );
         }
-        for (_i, inum) in (0..self.num_players).enumerate() {
-            for (_j, jnum) in (0..BOARD_SIZE).enumerate() {
-                for (_k, knum) in (0..BOARD_SIZE).enumerate() {
-                    if let Some(t) = self.surface[self.surface_index(inum, (jnum, knum))].as_ref() {
-                        t.draw_at(c, g, (jnum, knum));
-                    }
+    }
+
+    /// Tints the cells the rules mark, such as a hill or flags.
+    pub fn draw_zones<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
+        for (loc, tint) in self.rules.zones(self) {
+            Rectangle::new(tint).draw(layout.cell_rect(loc), &c.draw_state, c.transform, g);
+        }
+    }
+
+    /// Tints the ring sudden death is about to close, more strongly as its time runs out.
+    pub fn draw_collapse<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
+        let (ring, left) = match self.collapse_warning() {
+            Some(next) => next,
+            None => return,
+        };
+        let warning = self.sudden_death.map_or(0.0, |s| s.warning);
+        let closeness = if warning > 0.0 {
+            (1.0 - left / warning).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        let alpha = COLLAPSE_ALPHA_MIN + (COLLAPSE_ALPHA_MAX - COLLAPSE_ALPHA_MIN) * closeness;
+        let tint = [color::RED[0], color::RED[1], color::RED[2], alpha];
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if self.ring((col, row)) == ring && self.cell((col, row)) != Cell::Wall {
+                    Rectangle::new(tint).draw(
+                        layout.cell_rect((col, row)),
+                        &c.draw_state,
+                        c.transform,
+                        g,
+                    );
                 }
             }
         }
     }
+
+    /// Draws every token standing still in its cell; the game window animates them itself.
+    pub fn draw_tokens<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
+        for (pos, t) in self.tokens() {
+            t.draw_in(c, g, layout.token_rect(pos));
+        }
+    }
+
+    /// Cells an enemy of any of `viewers` could capture on, by its class or its promotion,
+    /// each with the enemy token that threatens it. Neutral tokens never move, so they
+    /// threaten nothing.
+    pub fn threatened_cells(&self, viewers: &[i32]) -> Vec<((i32, i32), &Token)> {
+        self.tokens()
+            .into_iter()
+            .filter(|(_pos, t)| t.owner() >= 0 && viewers.iter().any(|v| *v != t.owner()))
+            .flat_map(|(pos, t)| self.capture_cells(pos, t.captures()).map(move |n| (n, t)))
+            .collect()
+    }
+
+    /// Cells of the `viewers`' own tokens that an enemy could capture right now.
+    pub fn capturable_tokens(&self, viewers: &[i32]) -> Vec<(i32, i32)> {
+        let tokens = self.tokens();
+        tokens
+            .iter()
+            .filter(|(_pos, t)| viewers.contains(&t.owner()))
+            .filter(|(loc, mine)| {
+                tokens.iter().any(|(pos, t)| {
+                    t.owner() != mine.owner() && self.is_legal(t.owner(), *pos, *loc)
+                })
+            })
+            .map(|(loc, _t)| *loc)
+            .collect()
+    }
+
+    /// Tints the cells the `viewers`' enemies capture on in the enemy's color (stronger when
+    /// the enemy is Ready, i.e. able to capture there now) and rings the viewers' own tokens
+    /// that are currently capturable.
+    pub fn draw_threats<G: Graphics>(
+        &self,
+        c: &Context,
+        g: &mut G,
+        layout: &Layout,
+        viewers: &[i32],
+    ) {
+        for (cell, t) in self.threatened_cells(viewers) {
+            let alpha = if t.state == TokenStates::Ready {
+                THREAT_READY_ALPHA
+            } else {
+                THREAT_ALPHA
+            };
+            let tint = [t.color()[0], t.color()[1], t.color()[2], alpha];
+            Rectangle::new(tint).draw(layout.cell_rect(cell), &c.draw_state, c.transform, g);
+        }
+        for cell in self.capturable_tokens(viewers) {
+            let r = layout.cell_rect(cell);
+            Rectangle::new_border(color::RED, 3.0).draw(
+                [r[0] + 3.0, r[1] + 3.0, r[2] - 6.0, r[3] - 6.0],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Position, TokenClass};
+
+    /// Player 0 starts on the left and is one step from the right edge at (3, 0).
+    const NEAR_EDGE: &str = "\
+0r . . 0r .
+0r . . . 1r
+0r . . . 1r
+";
+
+    fn near_edge(promotion: bool) -> Board {
+        let mut board = Position::parse(NEAR_EDGE).unwrap().board;
+        board.set_promotion(promotion);
+        board
+    }
+
+    #[test]
+    fn undrained_events_are_capped_keeping_the_newest() {
+        let mut board = Board::new(2);
+        for i in 0..3 * MAX_EVENTS as i32 {
+            push_event(
+                &mut board.events,
+                BoardEvent::Moved {
+                    id: TokenId(0),
+                    from: (0, 0),
+                    to: (i, 0),
+                },
+            );
+        }
+        let events = board.drain_events();
+        assert!(events.len() <= MAX_EVENTS);
+        let newest = 3 * MAX_EVENTS as i32 - 1;
+        assert!(matches!(events.last(), Some(BoardEvent::Moved { to, .. }) if to.0 == newest));
+        assert!(board.drain_events().is_empty());
+    }
+
+    #[test]
+    fn tokens_reaching_the_far_edge_are_promoted() {
+        let mut board = near_edge(true);
+        board.drain_events();
+        assert_eq!(board.check_and_move_token(0, (3, 0), (4, 0)), None);
+        let t = board.token_at((4, 0)).unwrap();
+        assert!(t.promoted());
+        assert_eq!(t.captures(), TokenClass::Pawn.promoted_captures());
+        let events = board.drain_events();
+        assert!(events.iter().any(|e| matches!(
+            e,
+            BoardEvent::Promoted {
+                at: (4, 0),
+                owner: 0,
+                ..
+            }
+        )));
+
+        let mut board = near_edge(false);
+        board.check_and_move_token(0, (3, 0), (4, 0));
+        assert!(!board.token_at((4, 0)).unwrap().promoted());
+    }
+
+    #[test]
+    fn promoted_tokens_capture_more() {
+        let plain = Position::parse("0r 1r\n. .").unwrap().board;
+        assert!(!plain.is_legal(0, (0, 0), (1, 0)));
+        let promoted = Position::parse("0r+ 1r\n. .").unwrap().board;
+        assert!(promoted.is_legal(0, (0, 0), (1, 0)));
+        // steps are unchanged
+        assert!(!promoted.is_legal(0, (0, 0), (1, 1)));
+    }
+
+    #[test]
+    fn ownership_lives_on_the_token() {
+        let mut board = Board::empty(3, 2, 2);
+        assert!(board.place_token((0, 0), Token::new(TokenId(0), 0, [1.0; 4])));
+        assert!(board.place_token((2, 0), Token::new(TokenId(1), 1, [1.0; 4])));
+        assert!(board.place_token((1, 1), Token::new(TokenId(2), -1, [1.0; 4])));
+        // one token per cell, whoever owns it
+        assert!(!board.place_token((0, 0), Token::new(TokenId(3), 1, [1.0; 4])));
+        assert_eq!(board.token_at((2, 0)).map(|t| t.owner()), Some(1));
+        assert_eq!(board.tokens_remaining(0), 1);
+        assert_eq!(board.tokens_remaining(1), 1);
+        // the neutral token counts for nobody
+        assert_eq!(board.players_remaining(), 2);
+
+        board.set_owner((2, 0), 0);
+        assert_eq!(board.tokens_remaining(0), 2);
+        assert_eq!(board.tokens_remaining(1), 0);
+        assert_eq!(board.players_remaining(), 1);
+        assert_eq!(
+            board.find_token(TokenId(1)).map(|(pos, _t)| pos),
+            Some((2, 0))
+        );
+    }
+
+    #[test]
+    fn kills_report_the_victims_owner() {
+        let mut board = Position::parse("0r . 1r\n. 1r .").unwrap().board;
+        assert_eq!(board.kill_token_at((1, 1)), Some(1));
+        assert_eq!(board.kill_token_at((1, 1)), None);
+        assert!(board.token_at((1, 1)).is_none());
+        assert_eq!(board.tokens_remaining(1), 1);
+        assert_eq!(board.check_and_move_token(0, (0, 0), (1, 0)), None);
+        assert_eq!(board.tokens().len(), 2);
+    }
+
+    #[test]
+    fn sudden_death_closes_rings_until_the_middle() {
+        let text = "\
+0r . . . . .
+. 1r . . . .
+. . 0r . . .
+. . . 1r . .
+. . . . . .
+. . . . . .
+";
+        let mut board = Position::parse(text).unwrap().board;
+        board.set_sudden_death(Some(SuddenDeath {
+            start: 1.0,
+            every: 1.0,
+            warning: 0.5,
+        }));
+        board.drain_events();
+        assert_eq!(board.next_collapse(), Some((0, 1.0)));
+        assert_eq!(board.collapse_warning(), None);
+        board.update(0.6);
+        assert!(matches!(board.collapse_warning(), Some((0, left)) if left < 0.5));
+
+        board.update(0.5);
+        assert_eq!(board.collapsed_rings(), 1);
+        assert_eq!(board.cell((0, 0)), Cell::Wall);
+        assert_eq!(board.cell((5, 3)), Cell::Wall);
+        assert_eq!(board.cell((1, 1)), Cell::Floor);
+        assert!(board.token_at((0, 0)).is_none());
+        assert!(board
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, BoardEvent::Killed { at: (0, 0), .. })));
+        assert_eq!(board.tokens_remaining(0), 1);
+        // nobody is credited with the arena's kills
+        assert_eq!((board.kills(0), board.kills(1)), (0, 0));
+
+        board.update(1.0);
+        assert_eq!(board.collapsed_rings(), 2);
+        assert!(board.token_at((1, 1)).is_none());
+        // the middle two rows and columns never close
+        assert_eq!(board.next_collapse(), None);
+        board.update(10.0);
+        assert_eq!(board.collapsed_rings(), 2);
+        assert_eq!(board.cell((2, 2)), Cell::Floor);
+        assert_eq!(board.tokens().len(), 2);
+    }
+
+    #[test]
+    fn threats_are_shown_from_the_viewers_side() {
+        // player 1's pawn at (1, 1) is Ready, the one at (3, 1) and player 0's are not
+        let board = Position::parse("0w:0/4 . . . .\n. 1r . 1w:0/4 .\n. . 0w:0/4 . .")
+            .unwrap()
+            .board;
+        let mut threatened: Vec<((i32, i32), i32, bool)> = board
+            .threatened_cells(&[0])
+            .into_iter()
+            .map(|(cell, t)| (cell, t.owner(), t.state == TokenStates::Ready))
+            .collect();
+        threatened.sort();
+        assert_eq!(
+            threatened,
+            vec![
+                ((0, 0), 1, true),
+                ((0, 2), 1, true),
+                ((2, 0), 1, false),
+                ((2, 0), 1, true),
+                ((2, 2), 1, false),
+                ((2, 2), 1, true),
+                ((4, 0), 1, false),
+                ((4, 2), 1, false),
+            ]
+        );
+        // only the Ready enemy captures, and only the viewer's own tokens are marked
+        let mut capturable = board.capturable_tokens(&[0]);
+        capturable.sort();
+        assert_eq!(capturable, vec![(0, 0), (2, 2)]);
+        assert!(board.capturable_tokens(&[1]).is_empty());
+        assert!(board
+            .threatened_cells(&[1])
+            .iter()
+            .all(|(_cell, t)| t.owner() == 0));
+        assert!(board.threatened_cells(&[]).is_empty());
+    }
 }
@@ -0,0 +1,28 @@
+use crate::game_objects::{Board, HumanPlayer};
+
+/// Anything that can sit in a seat and play: it sees the board every update and hands over
+/// moves when it has them.
+pub trait Controller {
+    fn player_num(&self) -> i32;
+
+    fn name(&self) -> String;
+
+    /// Called once per update before `take_move`, with seconds since the match started.
+    fn observe(&mut self, _board: &Board, _time: f64) {}
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))>;
+}
+
+impl Controller for HumanPlayer {
+    fn player_num(&self) -> i32 {
+        HumanPlayer::player_num(self)
+    }
+
+    fn name(&self) -> String {
+        HumanPlayer::name(self)
+    }
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        HumanPlayer::take_move(self)
+    }
+}
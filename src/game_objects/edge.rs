@@ -0,0 +1,72 @@
+use crate::game_objects::Board;
+
+/// A side of the board, as the goal of a player racing or promoting towards it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    /// The edge furthest from the centre of each player's tokens, `None` for players without
+    /// any. Called on a freshly laid out board this is the edge opposite their start.
+    pub fn far_edges(board: &Board) -> Vec<Option<Edge>> {
+        let (right, bottom) = ((board.cols - 1) as f64, (board.rows - 1) as f64);
+        homes(board)
+            .into_iter()
+            .map(|home| {
+                let (x, y) = home?;
+                let edges = [
+                    (Edge::Right, right - x),
+                    (Edge::Left, x),
+                    (Edge::Bottom, bottom - y),
+                    (Edge::Top, y),
+                ];
+                edges
+                    .iter()
+                    .fold(None, |best: Option<(Edge, f64)>, (edge, far)| match best {
+                        Some((_e, b)) if b >= *far => best,
+                        _ => Some((*edge, *far)),
+                    })
+                    .map(|(edge, _far)| edge)
+            })
+            .collect()
+    }
+
+    /// How far along a cell is towards the edge, 0 on the opposite edge and 1 on it. Both
+    /// edges move in as sudden death closes the outer rings.
+    pub fn progress(self, board: &Board, loc: (i32, i32)) -> f64 {
+        let inset = board.collapsed_rings();
+        let across = |at: i32, n: i32| (at - inset) as f64 / (n - 1 - 2 * inset).max(1) as f64;
+        match self {
+            Edge::Right => across(loc.0, board.cols),
+            Edge::Left => 1.0 - across(loc.0, board.cols),
+            Edge::Bottom => across(loc.1, board.rows),
+            Edge::Top => 1.0 - across(loc.1, board.rows),
+        }
+    }
+}
+
+/// The centre of each player's tokens, `None` for players without any.
+pub fn homes(board: &Board) -> Vec<Option<(f64, f64)>> {
+    (0..board.num_players())
+        .map(|p| {
+            let cells: Vec<(i32, i32)> = board
+                .tokens()
+                .iter()
+                .filter(|(_pos, t)| t.owner() == p)
+                .map(|(pos, _t)| *pos)
+                .collect();
+            if cells.is_empty() {
+                return None;
+            }
+            let n = cells.len() as f64;
+            Some((
+                cells.iter().map(|c| c.0 as f64).sum::<f64>() / n,
+                cells.iter().map(|c| c.1 as f64).sum::<f64>() / n,
+            ))
+        })
+        .collect()
+}
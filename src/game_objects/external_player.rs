@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::game_objects::{Board, Controller};
+use crate::protocol::{self, Reply};
+
+/// Seconds between position updates when nothing on the board has moved.
+const UPDATE_INTERVAL: f64 = 0.1;
+/// How many 10ms waits an engine gets to exit after `quit` before it is killed.
+const QUIT_GRACE_STEPS: u32 = 20;
+/// Moves kept from an engine that answers faster than the game takes them; older ones are
+/// dropped first.
+const MAX_QUEUED_MOVES: usize = 8;
+
+/// A seat played by another executable speaking the `protocol` over its stdin and stdout.
+pub struct ExternalPlayer {
+    player_num: i32,
+    name: String,
+    child: Child,
+    // writes go through a thread so an engine that stops reading cannot stall the game
+    to_engine: Option<Sender<String>>,
+    from_engine: Receiver<Reply>,
+    started: bool,
+    last_sent: Option<f64>,
+    last_layout: Vec<(u32, (i32, i32))>,
+    // moves for the last position sent, oldest first
+    moves: VecDeque<((i32, i32), (i32, i32))>,
+}
+
+impl ExternalPlayer {
+    /// Starts `command`, split on whitespace into the executable and its arguments.
+    pub fn spawn(p: i32, command: &str) -> Result<ExternalPlayer, String> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| String::from("empty bot command"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("could not start {}: {}", program, e))?;
+
+        let mut stdin = child.stdin.take().expect("child stdin is piped");
+        let (to_engine, outbox) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for msg in outbox {
+                if stdin.write_all(msg.as_bytes()).is_err() || stdin.flush().is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stdout = child.stdout.take().expect("child stdout is piped");
+        let (inbox, from_engine) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if let Some(reply) = protocol::parse_reply(&line) {
+                    if inbox.send(reply).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let _ = to_engine.send(protocol::hello());
+        Ok(ExternalPlayer {
+            player_num: p,
+            name: program.to_string(),
+            child,
+            to_engine: Some(to_engine),
+            from_engine,
+            started: false,
+            last_sent: None,
+            last_layout: Vec::new(),
+            moves: VecDeque::new(),
+        })
+    }
+
+    fn send(&self, msg: String) {
+        if let Some(to_engine) = self.to_engine.as_ref() {
+            let _ = to_engine.send(msg);
+        }
+    }
+}
+
+impl Controller for ExternalPlayer {
+    fn player_num(&self) -> i32 {
+        self.player_num
+    }
+
+    fn name(&self) -> String {
+        format!("Player {} ({})", self.player_num + 1, self.name)
+    }
+
+    fn observe(&mut self, board: &Board, time: f64) {
+        if !self.started {
+            self.send(protocol::new_game(board, self.player_num));
+            self.started = true;
+        }
+        for reply in self.from_engine.try_iter() {
+            match reply {
+                Reply::Name(name) => self.name = name,
+                Reply::Move(from, to) => {
+                    if self.moves.len() == MAX_QUEUED_MOVES {
+                        self.moves.pop_front();
+                    }
+                    self.moves.push_back((from, to));
+                }
+            }
+        }
+        // a move or capture is worth telling the engine about straight away
+        let layout: Vec<_> = board
+            .tokens()
+            .iter()
+            .map(|(pos, t)| (t.id().0, *pos))
+            .collect();
+        let changed = layout != self.last_layout;
+        let due = match self.last_sent {
+            Some(last) => time - last >= UPDATE_INTERVAL,
+            None => true,
+        };
+        if changed {
+            // anything still queued was meant for a board that no longer exists
+            self.moves.clear();
+        }
+        if changed || due {
+            self.send(protocol::position(board, time));
+            self.last_sent = Some(time);
+            self.last_layout = layout;
+        }
+    }
+
+    fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        self.moves.pop_front()
+    }
+}
+
+impl Drop for ExternalPlayer {
+    fn drop(&mut self) {
+        self.send(protocol::quit());
+        // closing the channel ends the writer thread, which closes the engine's stdin
+        self.to_engine = None;
+        // give a well-behaved engine a moment to exit on its own
+        for _ in 0..QUIT_GRACE_STEPS {
+            if let Ok(Some(_status)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    const WAIT_STEPS: u32 = 200;
+
+    /// Observes `board` until `done` holds, giving the engine's replies time to arrive.
+    fn observe_until(
+        player: &mut ExternalPlayer,
+        board: &Board,
+        done: impl Fn(&ExternalPlayer) -> bool,
+    ) {
+        for _ in 0..WAIT_STEPS {
+            player.observe(board, 0.0);
+            if done(player) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the engine never answered");
+    }
+
+    // `cat` echoes what it is sent, so its replies are whatever the test writes to it
+    #[test]
+    fn replies_are_read_and_moves_taken_in_order() {
+        let board = Position::parse("0r . .\n. . 1r").unwrap().board;
+        let mut player = ExternalPlayer::spawn(1, "cat").unwrap();
+        assert_eq!(player.name(), "Player 2 (cat)");
+        player.observe(&board, 0.0);
+        player.send(String::from("name Echo Bot\nmove 2 1 1 0\nmove 2 1 2 0\n"));
+        observe_until(&mut player, &board, |p| p.moves.len() == 2);
+        assert_eq!(player.name(), "Player 2 (Echo Bot)");
+        assert_eq!(player.take_move(), Some(((2, 1), (1, 0))));
+        assert_eq!(player.take_move(), Some(((2, 1), (2, 0))));
+        assert_eq!(player.take_move(), None);
+    }
+
+    #[test]
+    fn move_queue_is_bounded_and_cleared_by_a_new_position() {
+        let board = Position::parse("0r . .\n. . 1r").unwrap().board;
+        let mut player = ExternalPlayer::spawn(1, "cat").unwrap();
+        player.observe(&board, 0.0);
+        let sent = MAX_QUEUED_MOVES as i32 + 3;
+        let replies: String = (0..sent).map(|n| format!("move {} 0 0 0\n", n)).collect();
+        player.send(replies);
+        observe_until(&mut player, &board, |p| {
+            p.moves.back() == Some(&((sent - 1, 0), (0, 0)))
+        });
+        assert_eq!(player.moves.len(), MAX_QUEUED_MOVES);
+        assert_eq!(player.take_move(), Some(((3, 0), (0, 0))));
+
+        let moved = Position::parse("0r . .\n. 1r .").unwrap().board;
+        player.observe(&moved, 0.0);
+        assert_eq!(player.take_move(), None);
+    }
+}
@@ -3,24 +3,24 @@ use std::f64::consts;
 use piston_window::Key;
 use piston_window::{CircleArc, Context, Graphics};
 
-use crate::game_objects::Board;
 use crate::game_objects::InputTypes;
 use crate::game_objects::KeyboardStates;
 
 use crate::drawing::color;
 use crate::drawing::screen;
-use crate::game_objects::token::cell_pos_to_canvas_pos;
+use crate::drawing::Layout;
 
-const SCREEN_WIDTH: i64 = screen::WIDTH;
-const SCREEN_HEIGHT: i64 = screen::HEIGHT;
 const BOARD_SIZE: i32 = screen::SIZE;
 
-const CELL_HEIGHT: f64 = (SCREEN_HEIGHT / BOARD_SIZE as i64) as f64;
-const CELL_WIDTH: f64 = (SCREEN_WIDTH / BOARD_SIZE as i64) as f64;
-
-const TOKEN_SIZE: i32 = ((0.6 * (CELL_HEIGHT + CELL_WIDTH)) as i32 / 2) as i32;
-const SELECTOR_SIZE: i32 = (0.85 * (CELL_HEIGHT + CELL_WIDTH) / 2.0) as i32;
-const SELECTOR_OFFSET: i32 = (0.5 * (SELECTOR_SIZE - TOKEN_SIZE) as f64) as i32;
+/// Draws the ring that marks a selected cell.
+pub fn draw_selector<G: Graphics>(c: &Context, g: &mut G, layout: &Layout, pos: (i32, i32)) {
+    CircleArc::new(color::BRIGHTBLUE, 2.0, 0.0, 1.9999 * consts::PI).draw(
+        layout.selector_rect(pos),
+        &c.draw_state,
+        c.transform,
+        g,
+    );
+}
 
 pub struct HumanPlayer {
     selection: Option<(i32, i32)>,
@@ -111,41 +111,47 @@ impl HumanPlayer {
         }
     }
 
-    pub fn update(&mut self, board: &mut Board) {
-        if let Some(m) = self.move_buffer {
-            board.check_and_move_token(self.player_num, m.0, m.1);
-            self.move_buffer = None;
+    /// The keyboard cursor, if the player has used it.
+    pub fn cursor(&self) -> Option<(i32, i32)> {
+        self.moving_selection
+    }
+
+    /// The cell picked as the start of the next move.
+    pub fn selected(&self) -> Option<(i32, i32)> {
+        self.selection
+    }
+
+    /// Cells currently ringed by this player's selectors.
+    pub fn selections(&self) -> Vec<(i32, i32)> {
+        self.selection
+            .iter()
+            .chain(self.moving_selection.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn player_num(&self) -> i32 {
+        self.player_num
+    }
+
+    pub fn name(&self) -> String {
+        match self.input_type {
+            InputTypes::Mouse => format!("Player {} (Mouse)", self.player_num + 1),
+            InputTypes::Keyboard => format!("Player {} (Keys)", self.player_num + 1),
         }
     }
 
-    pub fn draw_selection<G: Graphics>(&self, c: &Context, g: &mut G) {
+    /// Hands over the move the player has finished entering, if any.
+    pub fn take_move(&mut self) -> Option<((i32, i32), (i32, i32))> {
+        self.move_buffer.take()
+    }
+
+    pub fn draw_selection<G: Graphics>(&self, c: &Context, g: &mut G, layout: &Layout) {
         if let Some(sel) = self.selection {
-            let canv_pos = cell_pos_to_canvas_pos(sel);
-            CircleArc::new(color::BRIGHTBLUE, 2.0, 0.0, 1.9999 * consts::PI).draw(
-                [
-                    (canv_pos.0 - SELECTOR_OFFSET) as f64,
-                    (canv_pos.1 - SELECTOR_OFFSET) as f64,
-                    (SELECTOR_SIZE) as f64,
-                    (SELECTOR_SIZE) as f64,
-                ],
-                &c.draw_state,
-                c.transform,
-                g,
-            );
+            draw_selector(c, g, layout, sel);
         }
         if let Some(sel) = self.moving_selection {
-            let canv_pos = cell_pos_to_canvas_pos(sel);
-            CircleArc::new(color::BRIGHTBLUE, 2.0, 0.0, 1.9999 * consts::PI).draw(
-                [
-                    (canv_pos.0 - SELECTOR_OFFSET) as f64,
-                    (canv_pos.1 - SELECTOR_OFFSET) as f64,
-                    (SELECTOR_SIZE) as f64,
-                    (SELECTOR_SIZE) as f64,
-                ],
-                &c.draw_state,
-                c.transform,
-                g,
-            );
+            draw_selector(c, g, layout, sel);
         }
     }
 
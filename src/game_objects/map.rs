@@ -0,0 +1,226 @@
+use crate::drawing::screen;
+
+/// What a board cell is made of, independent of any token standing on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Floor,
+    /// Impassable; nothing may stand on or move into it.
+    Wall,
+    /// Timers of a token standing here run at double speed, for Wait and Prep as well as
+    /// the Ready window.
+    Haste,
+    /// A token standing here cannot be captured.
+    Safe,
+}
+
+impl Cell {
+    fn from_char(c: char) -> Option<Cell> {
+        match c {
+            '.' => Some(Cell::Floor),
+            'X' => Some(Cell::Wall),
+            'H' => Some(Cell::Haste),
+            'S' => Some(Cell::Safe),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Cell::Floor => '.',
+            Cell::Wall => 'X',
+            Cell::Haste => 'H',
+            Cell::Safe => 'S',
+        }
+    }
+}
+
+/// A board's terrain and where each player's tokens may start. Saved as text:
+///
+/// ```text
+/// # two pillars in the middle
+/// name Pillars
+/// grid
+/// 1........2
+/// 1........2
+/// 1..X..X..2
+/// ```
+///
+/// with one grid line per row and one character per cell: `.` floor, `X` wall, `H` haste,
+/// `S` safe, and `1` to `4` a floor cell in that player's spawn zone. The grid's size is the
+/// board's size; every row must be the same length.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Map {
+    pub name: String,
+    pub cols: i32,
+    pub rows: i32,
+    cells: Vec<Cell>,
+    spawns: Vec<Vec<(i32, i32)>>,
+}
+
+impl Map {
+    /// A plain floor with no spawn zones.
+    pub fn open(cols: i32, rows: i32) -> Map {
+        Map {
+            name: String::from("open"),
+            cols,
+            rows,
+            cells: vec![Cell::Floor; (cols * rows) as usize],
+            spawns: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Map, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+        Map::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Map, String> {
+        let mut name = String::from("untitled");
+        let mut grid: Vec<(usize, &str)> = Vec::new();
+        let mut in_grid = false;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if in_grid {
+                grid.push((n, line));
+                continue;
+            }
+            let mut words = line.splitn(2, char::is_whitespace);
+            match words.next() {
+                Some("name") => name = words.next().unwrap_or("").trim().to_string(),
+                Some("grid") => in_grid = true,
+                _ => return Err(format!("line {}: cannot read '{}'", n + 1, line)),
+            }
+        }
+        if grid.is_empty() {
+            return Err(String::from("no grid"));
+        }
+
+        let cols = grid[0].1.chars().count() as i32;
+        let rows = grid.len() as i32;
+        let mut cells = Vec::with_capacity((cols * rows) as usize);
+        let mut spawns: Vec<Vec<(i32, i32)>> = Vec::new();
+        for (row, (n, line)) in grid.iter().enumerate() {
+            if line.chars().count() as i32 != cols {
+                return Err(format!("line {}: expected {} cells", n + 1, cols));
+            }
+            for (col, c) in line.chars().enumerate() {
+                let cell = match (Cell::from_char(c), c.to_digit(10)) {
+                    (Some(cell), _) => cell,
+                    (None, Some(p @ 1..=4)) => {
+                        let p = p as usize - 1;
+                        if spawns.len() <= p {
+                            spawns.resize(p + 1, Vec::new());
+                        }
+                        spawns[p].push((col as i32, row as i32));
+                        Cell::Floor
+                    }
+                    _ => return Err(format!("line {}: unknown cell '{}'", n + 1, c)),
+                };
+                cells.push(cell);
+            }
+        }
+        Ok(Map {
+            name,
+            cols,
+            rows,
+            cells,
+            spawns,
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("name {}\ngrid\n", self.name);
+        out += &self.grid_lines().join("\n");
+        out.push('\n');
+        out
+    }
+
+    /// The grid, one string per row, spawn zones included.
+    pub fn grid_lines(&self) -> Vec<String> {
+        let mut chars: Vec<char> = self.cells.iter().map(|c| c.to_char()).collect();
+        for (p, zone) in self.spawns.iter().enumerate() {
+            for loc in zone {
+                chars[(loc.1 * self.cols + loc.0) as usize] =
+                    std::char::from_digit(p as u32 + 1, 10).unwrap_or('.');
+            }
+        }
+        chars
+            .chunks(self.cols.max(1) as usize)
+            .map(|row| row.iter().collect())
+            .collect()
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Cells where player `p`'s tokens start, in reading order; empty if the map leaves
+    /// placement to the `Setup`.
+    pub fn spawn_zone(&self, p: i32) -> &[(i32, i32)] {
+        match self.spawns.get(p as usize) {
+            Some(zone) => zone,
+            None => &[],
+        }
+    }
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Map::open(screen::SIZE, screen::SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PILLARS: &str = "\
+# two pillars in the middle
+name Pillars
+grid
+1..H..2
+1.X.X.2
+1..S..2
+";
+
+    #[test]
+    fn parse_reads_cells_and_spawn_zones() {
+        let map = Map::parse(PILLARS).unwrap();
+        assert_eq!(map.name, "Pillars");
+        assert_eq!((map.cols, map.rows), (7, 3));
+        let cell = |col: i32, row: i32| map.cells()[(row * map.cols + col) as usize];
+        assert_eq!(cell(3, 0), Cell::Haste);
+        assert_eq!(cell(2, 1), Cell::Wall);
+        assert_eq!(cell(4, 1), Cell::Wall);
+        assert_eq!(cell(3, 2), Cell::Safe);
+        assert_eq!(cell(0, 0), Cell::Floor);
+        assert_eq!(map.spawn_zone(0), &[(0, 0), (0, 1), (0, 2)][..]);
+        assert_eq!(map.spawn_zone(1), &[(6, 0), (6, 1), (6, 2)][..]);
+        assert!(map.spawn_zone(2).is_empty());
+    }
+
+    #[test]
+    fn text_round_trips() {
+        let map = Map::parse(PILLARS).unwrap();
+        assert_eq!(Map::parse(&map.to_text()).unwrap(), map);
+        assert_eq!(map.grid_lines(), vec!["1..H..2", "1.X.X.2", "1..S..2"]);
+    }
+
+    #[test]
+    fn parse_rejects_bad_maps() {
+        for text in &[
+            "",
+            "name Empty\n",
+            "grid\n...\n..\n",
+            "grid\n.Q.\n",
+            "grid\n.5.\n",
+            "size 3\ngrid\n...\n",
+        ] {
+            assert!(Map::parse(text).is_err(), "accepted {:?}", text);
+        }
+    }
+}
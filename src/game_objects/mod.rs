@@ -1,7 +1,17 @@
 mod board;
+mod position;
+mod setup;
 mod token;
 
+mod controller;
+mod edge;
+mod external_player;
 mod human_player;
+mod map;
+mod modes;
+mod rules;
+mod sudden_death;
+mod token_class;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum TokenStates {
@@ -11,6 +21,31 @@ pub enum TokenStates {
     Dead,
 }
 
+/// Something that happened on the board which the renderer may want to animate.
+#[derive(Clone, Copy)]
+pub enum BoardEvent {
+    Moved {
+        id: TokenId,
+        from: (i32, i32),
+        to: (i32, i32),
+    },
+    Killed {
+        id: TokenId,
+        at: (i32, i32),
+        color: [f32; 4],
+    },
+    Ready {
+        id: TokenId,
+        at: (i32, i32),
+    },
+    Promoted {
+        id: TokenId,
+        at: (i32, i32),
+        owner: i32,
+    },
+}
+
+#[derive(Clone, Copy)]
 pub enum InputTypes {
     Mouse,
     Keyboard,
@@ -22,9 +57,23 @@ pub enum KeyboardStates {
 }
 
 pub mod player_constants {
+    use crate::drawing::color;
+
+    /// Tokens each player starts with unless a `Handicap` says otherwise.
     pub const MAX_TOKENS: i32 = 4;
+    pub const COLORS: [[f32; 4]; 4] = [color::GREEN, color::ORANGE, color::CYAN, color::WHITE];
 }
 
 pub use self::board::Board;
-pub use self::human_player::HumanPlayer;
-pub use self::token::Token;
+pub use self::controller::Controller;
+pub use self::edge::Edge;
+pub use self::external_player::ExternalPlayer;
+pub use self::human_player::{draw_selector, HumanPlayer};
+pub use self::map::{Cell, Map};
+pub use self::modes::{FlagRules, GameMode, HillRules, RaceRules, TimedRules};
+pub use self::position::Position;
+pub use self::rules::{ClassicRules, Rules};
+pub use self::setup::{Handicap, MatchOptions, Setup};
+pub use self::sudden_death::SuddenDeath;
+pub use self::token::{Token, TokenId};
+pub use self::token_class::TokenClass;
@@ -0,0 +1,591 @@
+use std::sync::Arc;
+
+use crate::drawing::color;
+use crate::game_objects::edge::homes;
+use crate::game_objects::player_constants;
+use crate::game_objects::{Board, Cell, ClassicRules, Edge, Rules};
+
+const DEFAULT_HILL_SECONDS: f64 = 30.0;
+const DEFAULT_TIMED_SECONDS: f64 = 180.0;
+
+const HILL_ALPHA: f32 = 0.35;
+const GOAL_ALPHA: f32 = 0.15;
+const FLAG_ALPHA: f32 = 0.6;
+
+fn tint(c: [f32; 4], alpha: f32) -> [f32; 4] {
+    [c[0], c[1], c[2], alpha]
+}
+
+fn player_tint(p: i32, alpha: f32) -> [f32; 4] {
+    let c = player_constants::COLORS
+        .get(p as usize)
+        .copied()
+        .unwrap_or(color::LIGHTGREY);
+    tint(c, alpha)
+}
+
+fn format_clock(seconds: f64) -> String {
+    let total = seconds.max(0.0).ceil() as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// The one player ahead of all others on `key`, if nobody shares the lead.
+fn sole_leader<F: Fn(i32) -> f64>(board: &Board, key: F) -> Option<i32> {
+    let best = (0..board.num_players()).map(&key).fold(f64::MIN, f64::max);
+    let mut leaders = (0..board.num_players()).filter(|p| key(*p) >= best);
+    match (leaders.next(), leaders.next()) {
+        (Some(p), None) => Some(p),
+        _ => None,
+    }
+}
+
+/// The open cell nearest a point, the first in reading order on a tie.
+fn nearest_floor(board: &Board, x: f64, y: f64) -> Option<(i32, i32)> {
+    (0..board.rows)
+        .flat_map(|row| (0..board.cols).map(move |col| (col, row)))
+        .filter(|loc| board.cell(*loc) != Cell::Wall)
+        .min_by(|a, b| {
+            let dist = |c: &(i32, i32)| (c.0 as f64 - x).powi(2) + (c.1 as f64 - y).powi(2);
+            dist(a).total_cmp(&dist(b))
+        })
+}
+
+/// How a match is won. Every mode except `Elimination` adds a goal of its own, and a player
+/// who wipes out everyone else still wins in any of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GameMode {
+    /// The last player with tokens wins.
+    #[default]
+    Elimination,
+    /// The first player to hold the centre alone for `seconds` in total wins.
+    Hill { seconds: f64 },
+    /// The first player to get a token to the edge opposite their start wins.
+    Race,
+    /// After `seconds`, the player with the most captures wins, then the one with the most
+    /// tokens left; players level on both draw.
+    Timed { seconds: f64 },
+    /// The first player to carry an enemy's flag back to their own wins. A flag is picked up
+    /// by stepping onto it and goes home when its carrier is captured.
+    Flag,
+}
+
+impl GameMode {
+    pub const NAMES: [&'static str; 5] = ["elimination", "hill", "race", "timed", "flag"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Elimination => "elimination",
+            GameMode::Hill { .. } => "hill",
+            GameMode::Race => "race",
+            GameMode::Timed { .. } => "timed",
+            GameMode::Flag => "flag",
+        }
+    }
+
+    /// Reads a mode name, with the seconds for `hill` and `timed` optionally after a colon,
+    /// e.g. `hill:45`.
+    pub fn parse(text: &str) -> Result<GameMode, String> {
+        let mut parts = text.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let seconds = match parts.next() {
+            Some(s) => Some(
+                s.parse::<f64>()
+                    .ok()
+                    .filter(|s| *s > 0.0)
+                    .ok_or_else(|| format!("cannot read seconds in mode '{}'", text))?,
+            ),
+            None => None,
+        };
+        match name {
+            "elimination" | "race" | "flag" if seconds.is_some() => {
+                Err(format!("mode '{}' takes no seconds", name))
+            }
+            "elimination" => Ok(GameMode::Elimination),
+            "hill" => Ok(GameMode::Hill {
+                seconds: seconds.unwrap_or(DEFAULT_HILL_SECONDS),
+            }),
+            "race" => Ok(GameMode::Race),
+            "timed" => Ok(GameMode::Timed {
+                seconds: seconds.unwrap_or(DEFAULT_TIMED_SECONDS),
+            }),
+            "flag" => Ok(GameMode::Flag),
+            _ => Err(format!(
+                "unknown mode '{}', expected one of {}",
+                text,
+                GameMode::NAMES.join(", ")
+            )),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        match self {
+            GameMode::Hill { seconds } | GameMode::Timed { seconds } => {
+                format!("{}:{}", self.name(), seconds)
+            }
+            _ => self.name().to_string(),
+        }
+    }
+
+    /// The rules for this mode on a freshly laid out board; goals that depend on where
+    /// players start, like race edges and flags, are fixed from it.
+    pub fn rules(&self, board: &Board) -> Arc<dyn Rules> {
+        match *self {
+            GameMode::Elimination => Arc::new(ClassicRules),
+            GameMode::Hill { seconds } => Arc::new(HillRules::new(board, seconds)),
+            GameMode::Race => Arc::new(RaceRules::new(board)),
+            GameMode::Timed { seconds } => Arc::new(TimedRules { seconds }),
+            GameMode::Flag => Arc::new(FlagRules::new(board)),
+        }
+    }
+}
+
+/// King of the hill: the centre cells score a second per second for the player whose tokens
+/// are the only ones on them.
+pub struct HillRules {
+    seconds: f64,
+    hill: Vec<(i32, i32)>,
+}
+
+impl HillRules {
+    pub fn new(board: &Board, seconds: f64) -> HillRules {
+        // the middle cell of an odd side, the middle two of an even one
+        let middle = |n: i32| (n - 1) / 2..=n / 2;
+        let hill = middle(board.rows)
+            .flat_map(|row| middle(board.cols).map(move |col| (col, row)))
+            .filter(|loc| board.cell(*loc) != Cell::Wall)
+            .collect();
+        HillRules { seconds, hill }
+    }
+}
+
+impl Rules for HillRules {
+    fn name(&self) -> &str {
+        "hill"
+    }
+
+    fn on_tick(&self, board: &mut Board, dt: f64) {
+        let mut holders = self
+            .hill
+            .iter()
+            .filter_map(|loc| board.token_at(*loc).map(|t| t.owner()));
+        if let Some(first) = holders.next() {
+            if holders.all(|p| p == first) {
+                board.add_score(first, dt);
+            }
+        }
+    }
+
+    fn winner(&self, board: &Board) -> Option<i32> {
+        (0..board.num_players())
+            .find(|p| board.score(*p) >= self.seconds)
+            .or_else(|| ClassicRules.winner(board))
+    }
+
+    fn victory(&self, board: &Board, winner: i32) -> String {
+        if board.score(winner) >= self.seconds {
+            String::from("held the hill")
+        } else {
+            ClassicRules.victory(board, winner)
+        }
+    }
+
+    fn zones(&self, _board: &Board) -> Vec<((i32, i32), [f32; 4])> {
+        self.hill
+            .iter()
+            .map(|loc| (*loc, tint(color::YELLOW, HILL_ALPHA)))
+            .collect()
+    }
+
+    fn status(&self, board: &Board) -> Option<String> {
+        let held: Vec<String> = (0..board.num_players())
+            .map(|p| format!("P{} {:.0}s", p + 1, board.score(p)))
+            .collect();
+        Some(format!("hill: {} of {:.0}s", held.join("  "), self.seconds))
+    }
+}
+
+/// Race to the far edge: each player's goal is the board edge furthest from where their
+/// tokens started.
+pub struct RaceRules {
+    goals: Vec<Option<Edge>>,
+}
+
+impl RaceRules {
+    pub fn new(board: &Board) -> RaceRules {
+        RaceRules {
+            goals: Edge::far_edges(board),
+        }
+    }
+
+    fn best_progress(&self, board: &Board, p: i32) -> f64 {
+        let edge = match self.goals.get(p as usize).copied().flatten() {
+            Some(edge) => edge,
+            None => return 0.0,
+        };
+        board
+            .tokens()
+            .iter()
+            .filter(|(_pos, t)| t.owner() == p)
+            .map(|(pos, _t)| edge.progress(board, *pos))
+            .fold(0.0, f64::max)
+    }
+}
+
+impl Rules for RaceRules {
+    fn name(&self) -> &str {
+        "race"
+    }
+
+    fn winner(&self, board: &Board) -> Option<i32> {
+        (0..board.num_players())
+            .find(|p| self.best_progress(board, *p) >= 1.0)
+            .or_else(|| ClassicRules.winner(board))
+    }
+
+    fn victory(&self, board: &Board, winner: i32) -> String {
+        if self.best_progress(board, winner) >= 1.0 {
+            String::from("reached the far edge")
+        } else {
+            ClassicRules.victory(board, winner)
+        }
+    }
+
+    fn zones(&self, board: &Board) -> Vec<((i32, i32), [f32; 4])> {
+        let mut out = Vec::new();
+        for (p, goal) in self.goals.iter().enumerate() {
+            let edge = match goal {
+                Some(edge) => *edge,
+                None => continue,
+            };
+            let fill = player_tint(p as i32, GOAL_ALPHA);
+            for row in 0..board.rows {
+                for col in 0..board.cols {
+                    if board.ring((col, row)) >= board.collapsed_rings()
+                        && edge.progress(board, (col, row)) >= 1.0
+                    {
+                        out.push(((col, row), fill));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn status(&self, board: &Board) -> Option<String> {
+        let progress: Vec<String> = (0..board.num_players())
+            .map(|p| format!("P{} {:.0}%", p + 1, 100.0 * self.best_progress(board, p)))
+            .collect();
+        Some(format!("race: {}", progress.join("  ")))
+    }
+}
+
+/// Timed match scored by captures, as counted by `Board::kills`.
+pub struct TimedRules {
+    seconds: f64,
+}
+
+impl TimedRules {
+    /// The sole leader on captures once time is up.
+    fn most_captures(&self, board: &Board) -> Option<i32> {
+        if board.elapsed() < self.seconds {
+            return None;
+        }
+        sole_leader(board, |p| board.kills(p) as f64)
+    }
+
+    /// The sole leader on surviving tokens once time is up, for when captures are level.
+    fn most_tokens(&self, board: &Board) -> Option<i32> {
+        if board.elapsed() < self.seconds {
+            return None;
+        }
+        sole_leader(board, |p| board.tokens_remaining(p) as f64)
+    }
+}
+
+impl Rules for TimedRules {
+    fn name(&self) -> &str {
+        "timed"
+    }
+
+    fn winner(&self, board: &Board) -> Option<i32> {
+        ClassicRules
+            .winner(board)
+            .or_else(|| self.most_captures(board))
+            .or_else(|| self.most_tokens(board))
+    }
+
+    fn drawn(&self, board: &Board) -> Option<String> {
+        if board.elapsed() < self.seconds || self.winner(board).is_some() {
+            return None;
+        }
+        Some(String::from("time up, level on captures and tokens"))
+    }
+
+    fn victory(&self, board: &Board, winner: i32) -> String {
+        if self.most_captures(board) == Some(winner) {
+            format!("most captures ({})", board.kills(winner))
+        } else if self.most_tokens(board) == Some(winner) {
+            format!(
+                "most tokens left ({}), level on captures",
+                board.tokens_remaining(winner)
+            )
+        } else {
+            ClassicRules.victory(board, winner)
+        }
+    }
+
+    fn status(&self, board: &Board) -> Option<String> {
+        let left = self.seconds - board.elapsed();
+        if left > 0.0 {
+            Some(format!("most captures wins in {}", format_clock(left)))
+        } else {
+            Some(String::from("time up"))
+        }
+    }
+}
+
+/// Capture the flag: each player's flag sits on the floor cell nearest the centre of their
+/// starting tokens.
+pub struct FlagRules {
+    flags: Vec<Option<(i32, i32)>>,
+}
+
+impl FlagRules {
+    pub fn new(board: &Board) -> FlagRules {
+        let flags = homes(board)
+            .into_iter()
+            .map(|home| {
+                let (x, y) = home?;
+                nearest_floor(board, x, y)
+            })
+            .collect();
+        FlagRules { flags }
+    }
+
+    /// Where `p`'s flag is kept: its starting cell, or the open cell nearest that once sudden
+    /// death has walled it over.
+    fn flag_of(&self, board: &Board, p: i32) -> Option<(i32, i32)> {
+        let home = self.flags.get(p as usize).copied().flatten()?;
+        if board.cell(home) != Cell::Wall {
+            return Some(home);
+        }
+        nearest_floor(board, home.0 as f64, home.1 as f64)
+    }
+
+    fn carrier(board: &Board, flag: i32) -> Option<((i32, i32), i32)> {
+        board
+            .tokens()
+            .iter()
+            .find(|(_pos, t)| t.flag() == Some(flag))
+            .map(|(pos, t)| (*pos, t.owner()))
+    }
+}
+
+impl Rules for FlagRules {
+    fn name(&self) -> &str {
+        "flag"
+    }
+
+    fn on_move(&self, board: &mut Board, p: i32, to: (i32, i32), _victim: Option<i32>) {
+        let carrying = match board.token_at(to) {
+            Some(t) => t.flag(),
+            None => return,
+        };
+        match carrying {
+            Some(_flag) if self.flag_of(board, p) == Some(to) => {
+                board.add_score(p, 1.0);
+                board.set_flag(to, None);
+            }
+            Some(_flag) => {}
+            None => {
+                let taken = (0..board.num_players()).find(|q| {
+                    *q != p
+                        && self.flag_of(board, *q) == Some(to)
+                        && FlagRules::carrier(board, *q).is_none()
+                });
+                if taken.is_some() {
+                    board.set_flag(to, taken);
+                }
+            }
+        }
+    }
+
+    fn winner(&self, board: &Board) -> Option<i32> {
+        (0..board.num_players())
+            .find(|p| board.score(*p) >= 1.0)
+            .or_else(|| ClassicRules.winner(board))
+    }
+
+    fn victory(&self, board: &Board, winner: i32) -> String {
+        if board.score(winner) >= 1.0 {
+            String::from("captured the flag")
+        } else {
+            ClassicRules.victory(board, winner)
+        }
+    }
+
+    fn zones(&self, board: &Board) -> Vec<((i32, i32), [f32; 4])> {
+        // a flag is drawn at home, or under whoever is carrying it
+        (0..board.num_players())
+            .filter_map(|p| {
+                let at = match FlagRules::carrier(board, p) {
+                    Some((pos, _owner)) => pos,
+                    None => self.flag_of(board, p)?,
+                };
+                Some((at, player_tint(p, FLAG_ALPHA)))
+            })
+            .collect()
+    }
+
+    fn status(&self, board: &Board) -> Option<String> {
+        let carried: Vec<String> = (0..board.num_players())
+            .filter_map(|flag| {
+                FlagRules::carrier(board, flag)
+                    .map(|(_pos, owner)| format!("P{} has P{}'s flag", owner + 1, flag + 1))
+            })
+            .collect();
+        if carried.is_empty() {
+            Some(String::from("flag: bring an enemy flag home"))
+        } else {
+            Some(format!("flag: {}", carried.join("  ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::Position;
+
+    const LINED_UP: &str = "\
+0r . . . 1r
+0r . . . 1r
+0r . . . 1r
+";
+
+    fn board(text: &str) -> Board {
+        Position::parse(text).unwrap().board
+    }
+
+    fn with_mode(mut board: Board, mode: GameMode, start: &Board) -> Board {
+        board.set_rules(mode.rules(start));
+        board
+    }
+
+    #[test]
+    fn parse_reads_seconds_only_where_a_mode_takes_them() {
+        assert_eq!(
+            GameMode::parse("hill:45"),
+            Ok(GameMode::Hill { seconds: 45.0 })
+        );
+        assert_eq!(
+            GameMode::parse("timed"),
+            Ok(GameMode::Timed {
+                seconds: DEFAULT_TIMED_SECONDS
+            })
+        );
+        assert_eq!(GameMode::parse("race"), Ok(GameMode::Race));
+        for text in &[
+            "race:30",
+            "flag:1",
+            "elimination:5",
+            "hill:0",
+            "timed:x",
+            "chess",
+        ] {
+            assert!(GameMode::parse(text).is_err(), "accepted {}", text);
+        }
+        for mode in &[GameMode::Flag, GameMode::Timed { seconds: 90.0 }] {
+            assert_eq!(GameMode::parse(&mode.to_text()), Ok(*mode));
+        }
+    }
+
+    #[test]
+    fn elimination_is_won_by_the_last_player_with_tokens() {
+        let start = board(LINED_UP);
+        let b = with_mode(board(LINED_UP), GameMode::Elimination, &start);
+        assert_eq!(b.winner(), None);
+        let b = with_mode(board("0r . .\n. . ."), GameMode::Elimination, &start);
+        assert_eq!(b.winner(), Some(0));
+        assert_eq!(b.rules().victory(&b, 0), "last player standing");
+    }
+
+    #[test]
+    fn hill_is_won_by_holding_the_centre_alone() {
+        let mode = GameMode::Hill { seconds: 1.0 };
+        let contested = ". . . .\n. 0r . .\n. . 1r .\n. . . .";
+        let mut b = with_mode(board(contested), mode, &board(contested));
+        b.update(2.0);
+        assert_eq!(b.winner(), None);
+
+        let alone = "1r . . .\n. 0r . .\n. . . .\n. . . .";
+        let mut b = with_mode(board(alone), mode, &board(alone));
+        b.update(0.5);
+        assert_eq!(b.winner(), None);
+        b.update(0.5);
+        assert_eq!(b.winner(), Some(0));
+        assert_eq!(b.rules().victory(&b, 0), "held the hill");
+    }
+
+    #[test]
+    fn race_is_won_by_reaching_the_far_edge() {
+        let start = board(LINED_UP);
+        let b = with_mode(board(LINED_UP), GameMode::Race, &start);
+        assert_eq!(b.winner(), None);
+        let arrived = "0r . . . 1r\n. . . . 0r\n. . . . 1r";
+        let b = with_mode(board(arrived), GameMode::Race, &start);
+        assert_eq!(b.winner(), Some(0));
+        assert_eq!(b.rules().victory(&b, 0), "reached the far edge");
+    }
+
+    #[test]
+    fn timed_is_won_on_captures_then_tokens_or_drawn() {
+        let mode = GameMode::Timed { seconds: 1.0 };
+        let start = board(LINED_UP);
+
+        let mut b = with_mode(board("0r . . 0r\n. 1r . ."), mode, &start);
+        assert_eq!(b.check_and_move_token(1, (1, 1), (0, 0)), Some(0));
+        assert_eq!(b.winner(), None);
+        b.update(1.0);
+        assert_eq!(b.winner(), Some(1));
+        assert_eq!(b.rules().victory(&b, 1), "most captures (1)");
+
+        let mut b = with_mode(board("0r . 1r\n0r . ."), mode, &start);
+        b.update(1.0);
+        assert_eq!(b.winner(), Some(0));
+        assert_eq!(
+            b.rules().victory(&b, 0),
+            "most tokens left (2), level on captures"
+        );
+        assert_eq!(b.drawn(), None);
+
+        let mut b = with_mode(board(LINED_UP), mode, &start);
+        assert_eq!(b.drawn(), None);
+        b.update(1.0);
+        assert_eq!(b.winner(), None);
+        assert!(b.drawn().is_some());
+    }
+
+    #[test]
+    fn flag_is_won_by_bringing_an_enemy_flag_home() {
+        let start = board(LINED_UP);
+        let mut b = with_mode(
+            board("0r . . . 1r\n. . . 0r .\n0r . . . 1r"),
+            GameMode::Flag,
+            &start,
+        );
+        b.check_and_move_token(0, (3, 1), (4, 1));
+        assert_eq!(b.token_at((4, 1)).and_then(|t| t.flag()), Some(1));
+        assert_eq!(b.winner(), None);
+
+        let mut b = with_mode(
+            board("0r . . . 1r\n. 0r . . .\n0r . . . 1r"),
+            GameMode::Flag,
+            &start,
+        );
+        b.set_flag((1, 1), Some(1));
+        b.check_and_move_token(0, (1, 1), (0, 1));
+        assert_eq!(b.winner(), Some(0));
+        assert_eq!(b.rules().victory(&b, 0), "captured the flag");
+    }
+}
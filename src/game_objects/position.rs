@@ -0,0 +1,236 @@
+use crate::game_objects::player_constants;
+use crate::game_objects::{Board, Token, TokenClass, TokenId, TokenStates};
+
+const MIN_PLAYERS: i32 = 2;
+
+/// A board snapshot plus any highlighted cells, in a plain text form that can be pasted into
+/// bug reports and read back. One line per board row, cells separated by whitespace:
+///
+/// ```text
+/// # comments start with '#'
+/// .  .    0r   .
+/// .  1pJ:4.5  .  0w:0.5/4
+/// select 2 0
+/// ```
+///
+/// `.` is an empty cell. A token is its owner's number followed by `w`, `p` or `r` for
+/// Wait, Prep or Ready, then its class letter (`R`, `J` or `G`; none for a pawn), `+` if it
+/// has been promoted, optionally `:seconds` into that state and, for Wait, `/seconds` of
+/// total wait. `select <col> <row>` lines ring a cell the way a player's selection does.
+pub struct Position {
+    pub board: Board,
+    pub selections: Vec<(i32, i32)>,
+}
+
+fn parse_token(cell: &str, id: u32) -> Result<Token, String> {
+    let bad = || format!("bad cell '{}'", cell);
+    let (head, timing) = match cell.find(':') {
+        Some(i) => (&cell[..i], Some(&cell[i + 1..])),
+        None => (cell, None),
+    };
+    let (head, promoted) = match head.strip_suffix('+') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (head, class) = match head.chars().last().filter(char::is_ascii_uppercase) {
+        Some(c) => (
+            &head[..head.len() - 1],
+            TokenClass::from_letter(c).ok_or_else(bad)?,
+        ),
+        None => (head, TokenClass::Pawn),
+    };
+    // the state letter is the last char, which need not be one byte in a malformed cell
+    let (state_at, state) = head.char_indices().last().ok_or_else(bad)?;
+    let owner: i32 = head[..state_at].parse().map_err(|_| bad())?;
+    if owner < 0 || owner as usize >= player_constants::COLORS.len() {
+        return Err(format!("no color for player {} in '{}'", owner, cell));
+    }
+    let state = match state {
+        'w' => TokenStates::Wait,
+        'p' => TokenStates::Prep,
+        'r' => TokenStates::Ready,
+        _ => return Err(bad()),
+    };
+    let mut t = Token::new(TokenId(id), owner, player_constants::COLORS[owner as usize]);
+    t.set_state(state);
+    t.set_class(class);
+    t.set_promoted(promoted);
+    if let Some(timing) = timing {
+        let mut parts = timing.splitn(2, '/');
+        let time = parts.next().unwrap_or("");
+        t.set_time(time.parse().map_err(|_| bad())?);
+        if let Some(wait) = parts.next() {
+            t.set_wait_time(wait.parse().map_err(|_| bad())?);
+        }
+    }
+    Ok(t)
+}
+
+fn format_token(t: &Token) -> String {
+    let state = match t.state {
+        TokenStates::Wait => "w",
+        TokenStates::Prep => "p",
+        TokenStates::Ready => "r",
+        TokenStates::Dead => return String::from("."),
+    };
+    let mut out = format!("{}{}", t.owner(), state);
+    if t.class() != TokenClass::Pawn {
+        out.push(t.class().letter());
+    }
+    if t.promoted() {
+        out.push('+');
+    }
+    if t.time() > 0.0 || t.state == TokenStates::Wait {
+        out += &format!(":{}", t.time());
+    }
+    if t.state == TokenStates::Wait {
+        out += &format!("/{}", t.wait_time);
+    }
+    out
+}
+
+impl Position {
+    /// The classic start for `players` once every token is through its first Wait and Prep,
+    /// so each side has all its moves. Shared by the searches' tests and benchmarks.
+    pub fn all_ready(players: i32) -> Position {
+        let mut board = Board::new(players);
+        board.update(1.0);
+        board.update(10.0);
+        Position {
+            board,
+            selections: Vec::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Position, String> {
+        let mut rows: Vec<Vec<&str>> = Vec::new();
+        let mut selections = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words[0] == "select" {
+                match (
+                    words.get(1).and_then(|w| w.parse().ok()),
+                    words.get(2).and_then(|w| w.parse().ok()),
+                ) {
+                    (Some(col), Some(row)) => selections.push((col, row)),
+                    _ => return Err(format!("line {}: expected 'select <col> <row>'", n + 1)),
+                }
+                continue;
+            }
+            rows.push(words);
+        }
+        let cols = rows.first().map_or(0, |r| r.len());
+        if cols == 0 {
+            return Err(String::from("position has no rows"));
+        }
+        if let Some(r) = rows.iter().position(|r| r.len() != cols) {
+            return Err(format!(
+                "row {} has {} cells, expected {}",
+                r + 1,
+                rows[r].len(),
+                cols
+            ));
+        }
+
+        let mut tokens = Vec::new();
+        let mut num_players = MIN_PLAYERS;
+        for (row, cells) in rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if *cell == "." {
+                    continue;
+                }
+                let t = parse_token(cell, tokens.len() as u32)?;
+                num_players = num_players.max(t.owner() + 1);
+                tokens.push(((col as i32, row as i32), t));
+            }
+        }
+        let mut board = Board::empty(cols as i32, rows.len() as i32, num_players);
+        for (pos, t) in tokens {
+            board.place_token(pos, t);
+        }
+        Ok(Position { board, selections })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.board.rows {
+            let cells: Vec<String> = (0..self.board.cols)
+                .map(|col| match self.board.token_at((col, row)) {
+                    Some(t) => format_token(t),
+                    None => String::from("."),
+                })
+                .collect();
+            out += &cells.join(" ");
+            out.push('\n');
+        }
+        for sel in self.selections.iter() {
+            out += &format!("select {} {}\n", sel.0, sel.1);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+0r . 1pJ:4.5 .
+. 0w:0.5/4 . 1rG+
+0pR+:2 . . 1w:1/4
+select 2 0
+";
+
+    #[test]
+    fn text_round_trips() {
+        let position = Position::parse(SAMPLE).unwrap();
+        assert_eq!(position.to_text(), SAMPLE);
+        let again = Position::parse(&position.to_text()).unwrap();
+        assert_eq!(again.to_text(), SAMPLE);
+    }
+
+    #[test]
+    fn parse_reads_every_field() {
+        let position = Position::parse(SAMPLE).unwrap();
+        let board = &position.board;
+        assert_eq!((board.cols, board.rows), (4, 3));
+        assert_eq!(board.num_players(), 2);
+        assert_eq!(position.selections, vec![(2, 0)]);
+
+        let jumper = board.token_at((2, 0)).unwrap();
+        assert_eq!(jumper.owner(), 1);
+        assert!(jumper.state == TokenStates::Prep);
+        assert_eq!(jumper.class(), TokenClass::Jumper);
+        assert_eq!(jumper.time(), 4.5);
+        assert!(!jumper.promoted());
+
+        let waiting = board.token_at((1, 1)).unwrap();
+        assert!(waiting.state == TokenStates::Wait);
+        assert_eq!(waiting.wait_time, 4.0);
+
+        let guard = board.token_at((3, 1)).unwrap();
+        assert_eq!(guard.class(), TokenClass::Guard);
+        assert!(guard.promoted());
+    }
+
+    #[test]
+    fn parse_rejects_bad_cells() {
+        for text in ["1é", "é", "1x", "0rQ", "9r", "0r:x", ". 0r\n."] {
+            assert!(Position::parse(text).is_err(), "accepted '{}'", text);
+        }
+    }
+
+    #[test]
+    fn all_ready_starts_with_every_token_ready() {
+        let board = Position::all_ready(2).board;
+        assert_eq!(board.tokens().len(), Board::new(2).tokens().len());
+        assert!(board
+            .tokens()
+            .iter()
+            .all(|(_pos, t)| t.state == TokenStates::Ready));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::game_objects::{Board, Cell, Token, TokenStates};
+
+/// Seconds a token waits after moving, before its cooldown multipliers.
+const MOVE_WAIT: f64 = 4.0;
+
+/// The parts of the game a variant may change. `Board` asks its rules whenever a move is
+/// tried or the match might be over, and tells them when time passes or a move is made so
+/// they can keep score on the board; every method has the standard game as its default, so
+/// a variant only overrides what it changes.
+pub trait Rules: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Whether `p` may move the token at `from` to `to` right now. The token must be Ready
+    /// and its class, or for a capture its promotion, must allow the offset: a step onto an
+    /// empty cell, or a capture of an enemy that is not on a safe cell. Nothing may end on a
+    /// wall, and unless the class leaps, every cell passed over on a straight line must be
+    /// empty floor.
+    fn is_legal(&self, board: &Board, p: i32, from: (i32, i32), to: (i32, i32)) -> bool {
+        if !board.in_bounds(from) || !board.in_bounds(to) || board.cell(to) == Cell::Wall {
+            return false;
+        }
+        let mover = match board.token_at(from) {
+            Some(t) if t.owner() == p && t.state == TokenStates::Ready => t,
+            _ => return false,
+        };
+        let offset = (to.0 - from.0, to.1 - from.1);
+        let allowed = match board.token_at(to) {
+            None => mover.class().steps().contains(&offset),
+            Some(t) => {
+                t.owner() != p && board.cell(to) != Cell::Safe && mover.captures().contains(&offset)
+            }
+        };
+        allowed && (mover.class().leaps() || board.path_clear(from, to))
+    }
+
+    /// Every cell the token at `from` could legally move to right now, captures first.
+    fn targets(&self, board: &Board, p: i32, from: (i32, i32)) -> Vec<(i32, i32)> {
+        let (captures, steps) = match board.token_at(from) {
+            Some(t) => (t.captures(), t.class().steps()),
+            None => return Vec::new(),
+        };
+        let mut out = Vec::new();
+        for d in captures.iter().chain(steps.iter()) {
+            let to = (from.0 + d.0, from.1 + d.1);
+            // a class may list the same offset as both a step and a capture
+            if !out.contains(&to) && self.is_legal(board, p, from, to) {
+                out.push(to);
+            }
+        }
+        out
+    }
+
+    /// The cell whose token a legal move captures, if any; the destination's occupant by
+    /// default. The mover's token swaps places with whatever is left on `to`, so a variant
+    /// that captures elsewhere must only allow such moves onto an empty cell.
+    fn captured_cell(
+        &self,
+        board: &Board,
+        _p: i32,
+        _from: (i32, i32),
+        to: (i32, i32),
+    ) -> Option<(i32, i32)> {
+        board.token_at(to).map(|_t| to)
+    }
+
+    /// Puts a token that has just moved into its next state: back to Wait for a while.
+    fn after_move(&self, token: &mut Token) {
+        token.state = TokenStates::Wait;
+        token.set_wait_time(MOVE_WAIT);
+    }
+
+    /// Called after every board update, once the tokens' timers have run for `dt` seconds.
+    fn on_tick(&self, _board: &mut Board, _dt: f64) {}
+
+    /// Called after `p`'s token has moved to `to`, capturing one of `victim`'s tokens if any.
+    fn on_move(&self, _board: &mut Board, _p: i32, _to: (i32, i32), _victim: Option<i32>) {}
+
+    /// The player who has won, if the match is decided: the last one with tokens left.
+    fn winner(&self, board: &Board) -> Option<i32> {
+        if board.players_remaining() != 1 {
+            return None;
+        }
+        (0..board.num_players()).find(|p| board.tokens_remaining(*p) > 0)
+    }
+
+    /// Why the match has ended with no winner, if it has; never by default.
+    fn drawn(&self, _board: &Board) -> Option<String> {
+        None
+    }
+
+    /// How `winner` won, for the end screen.
+    fn victory(&self, _board: &Board, _winner: i32) -> String {
+        String::from("last player standing")
+    }
+
+    /// Cells to tint and their tint, such as objectives the players are fighting over.
+    fn zones(&self, _board: &Board) -> Vec<((i32, i32), [f32; 4])> {
+        Vec::new()
+    }
+
+    /// One line on how each player stands towards the goal, if the mode has more to say than
+    /// the token counts.
+    fn status(&self, _board: &Board) -> Option<String> {
+        None
+    }
+}
+
+/// The standard game, exactly as the defaults of `Rules` describe it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClassicRules;
+
+impl Rules for ClassicRules {
+    fn name(&self) -> &str {
+        "classic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Map, TokenClass, TokenId};
+
+    /// `Board::is_legal` as it was written before the rules moved into this trait.
+    fn board_is_legal(board: &Board, p: i32, from: (i32, i32), to: (i32, i32)) -> bool {
+        if !board.in_bounds(from) || !board.in_bounds(to) || board.cell(to) == Cell::Wall {
+            return false;
+        }
+        let class = match board.token_at(from) {
+            Some(t) if t.owner() == p && t.state == TokenStates::Ready => t.class(),
+            _ => return false,
+        };
+        let offset = (to.0 - from.0, to.1 - from.1);
+        let allowed = match board.token_at(to) {
+            None => class.steps().contains(&offset),
+            Some(t) => {
+                t.owner() != p && board.cell(to) != Cell::Safe && class.captures().contains(&offset)
+            }
+        };
+        allowed && (class.leaps() || board.path_clear(from, to))
+    }
+
+    /// Every class for both players, some not Ready, among walls and safe cells.
+    fn crowded() -> Board {
+        let map = Map::parse("grid\n.....\n.X.S.\n..S..\n.X...\n.....\n").unwrap();
+        let mut board = Board::on_map(&map, 2);
+        let placed = [
+            ((0, 0), 0, TokenClass::Pawn, TokenStates::Ready),
+            ((2, 0), 0, TokenClass::Runner, TokenStates::Ready),
+            ((4, 0), 0, TokenClass::Jumper, TokenStates::Ready),
+            ((0, 2), 0, TokenClass::Guard, TokenStates::Ready),
+            ((1, 2), 0, TokenClass::Pawn, TokenStates::Wait),
+            ((2, 2), 1, TokenClass::Pawn, TokenStates::Ready),
+            ((3, 1), 1, TokenClass::Guard, TokenStates::Ready),
+            ((2, 4), 1, TokenClass::Runner, TokenStates::Ready),
+            ((3, 3), 1, TokenClass::Jumper, TokenStates::Ready),
+            ((4, 4), 1, TokenClass::Pawn, TokenStates::Prep),
+            ((0, 4), 1, TokenClass::Guard, TokenStates::Ready),
+        ];
+        for (id, (loc, owner, class, state)) in placed.iter().enumerate() {
+            let mut t = Token::new(TokenId(id as u32), *owner, [1.0; 4]);
+            t.set_class(*class);
+            t.set_state(*state);
+            assert!(board.place_token(*loc, t));
+        }
+        board
+    }
+
+    #[test]
+    fn classic_rules_match_the_old_board_check() {
+        let board = crowded();
+        let cells: Vec<(i32, i32)> = (-1..=board.rows)
+            .flat_map(|row| (-1..=board.cols).map(move |col| (col, row)))
+            .collect();
+        let mut legal = 0;
+        for p in 0..2 {
+            for from in cells.iter() {
+                for to in cells.iter() {
+                    let expected = board_is_legal(&board, p, *from, *to);
+                    assert_eq!(
+                        ClassicRules.is_legal(&board, p, *from, *to),
+                        expected,
+                        "player {} from {:?} to {:?}",
+                        p,
+                        from,
+                        to
+                    );
+                    legal += expected as u32;
+                }
+                let mut targets = ClassicRules.targets(&board, p, *from);
+                targets.sort();
+                let mut expected: Vec<(i32, i32)> = cells
+                    .iter()
+                    .copied()
+                    .filter(|to| board_is_legal(&board, p, *from, *to))
+                    .collect();
+                expected.sort();
+                assert_eq!(targets, expected, "player {} from {:?}", p, from);
+            }
+        }
+        assert!(legal > 10, "only {} legal moves to compare", legal);
+    }
+
+    #[test]
+    fn classic_captures_take_the_destination() {
+        let mut board = crowded();
+        assert_eq!(ClassicRules.captured_cell(&board, 1, (2, 2), (2, 1)), None);
+        assert_eq!(
+            ClassicRules.captured_cell(&board, 0, (2, 0), (3, 1)),
+            Some((3, 1))
+        );
+        assert!(board.is_legal(0, (0, 2), (0, 3)));
+        assert_eq!(board.check_and_move_token(0, (0, 2), (0, 3)), None);
+        // captures come first
+        let (from, to) = board.legal_moves(1)[0];
+        assert_eq!(board.token_at(to).map(|t| t.owner()), Some(0));
+        assert_eq!(board.check_and_move_token(1, from, to), Some(0));
+        assert_eq!(board.token_at(to).map(|t| t.owner()), Some(1));
+        assert!(board.token_at(from).is_none());
+        assert_eq!(board.tokens_remaining(0), 4);
+    }
+}
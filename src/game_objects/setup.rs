@@ -0,0 +1,383 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::game_objects::player_constants;
+use crate::game_objects::{Board, Cell, GameMode, Map, SuddenDeath, Token, TokenClass, TokenId};
+
+const MAX_TOKENS: i32 = player_constants::MAX_TOKENS;
+
+/// Columns on a player's own side that `Scattered` and `Rotational` may place tokens in.
+const HOME_DEPTH: i32 = 3;
+
+/// A player's starting strength, set per match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Handicap {
+    pub tokens: i32,
+    /// Multiplies the player's Wait and Prep times; above 1.0 slows them down.
+    pub cooldown: f64,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Handicap {
+            tokens: MAX_TOKENS,
+            cooldown: 1.0,
+        }
+    }
+}
+
+impl Handicap {
+    /// Reads `<tokens>` or `<tokens>x<cooldown>`, e.g. `3x1.5`.
+    pub fn parse(text: &str) -> Result<Handicap, String> {
+        let bad = || {
+            format!(
+                "cannot read handicap '{}', expected <tokens>[x<cooldown>]",
+                text
+            )
+        };
+        let mut parts = text.splitn(2, 'x');
+        let tokens = parts
+            .next()
+            .and_then(|t| t.parse().ok())
+            .filter(|t| *t >= 0)
+            .ok_or_else(bad)?;
+        let cooldown = match parts.next() {
+            Some(c) => c.parse().ok().filter(|c: &f64| *c > 0.0).ok_or_else(bad)?,
+            None => 1.0,
+        };
+        Ok(Handicap { tokens, cooldown })
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("{}x{}", self.tokens, self.cooldown)
+    }
+}
+
+/// How tokens are placed at the start of a match. Every layout except `Classic` is drawn
+/// from a seed, and the same seed always gives the same layout. Each player's tokens are a
+/// copy of player 0's, mirrored or rotated onto their own side, so no side starts ahead.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Setup {
+    /// A centered column on the left and right edges.
+    #[default]
+    Classic,
+    /// Random rows on the left edge, mirrored onto the right edge.
+    Mirrored,
+    /// Random cells within the home zone, turned half way round for the other player.
+    Scattered,
+    /// Random cells within the home zone away from the corners, turned a quarter further
+    /// for each player, so up to four players each get an edge. Off the square, a turned
+    /// layout is stretched or squeezed to fit the edge it lands on.
+    Rotational,
+}
+
+impl Setup {
+    pub const ALL: [Setup; 4] = [
+        Setup::Classic,
+        Setup::Mirrored,
+        Setup::Scattered,
+        Setup::Rotational,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setup::Classic => "classic",
+            Setup::Mirrored => "mirrored",
+            Setup::Scattered => "scattered",
+            Setup::Rotational => "rotational",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Setup, String> {
+        Setup::ALL
+            .iter()
+            .find(|s| s.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Setup::ALL.iter().map(|s| s.name()).collect();
+                format!(
+                    "unknown layout '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    /// Cells on player 0's side that tokens may start on, in the order they are taken.
+    fn candidates(&self, cols: i32, rows: i32, rng: &mut StdRng) -> Vec<(i32, i32)> {
+        let mut cells: Vec<(i32, i32)> = match self {
+            Setup::Classic => return Vec::new(),
+            Setup::Mirrored => (0..rows).map(|row| (0, row)).collect(),
+            Setup::Scattered | Setup::Rotational => {
+                // rotated copies of a zone that reaches the corners would overlap
+                let margin = if *self == Setup::Rotational {
+                    HOME_DEPTH
+                } else {
+                    0
+                };
+                (margin..rows - margin)
+                    .flat_map(|row| (0..HOME_DEPTH.min(cols)).map(move |col| (col, row)))
+                    .collect()
+            }
+        };
+        cells.shuffle(rng);
+        cells
+    }
+
+    /// The first `count` of player 0's cells, in token order. Players with fewer tokens get
+    /// a subset of the same cells, so an uneven match differs only in the missing tokens.
+    fn pattern(&self, rows: i32, count: i32, candidates: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        match self {
+            Setup::Classic => {
+                let count = count.min(rows);
+                let offset = (rows - count) / 2;
+                (0..count).map(|j| (0, j + offset)).collect()
+            }
+            _ => {
+                let mut cells: Vec<(i32, i32)> = candidates
+                    .iter()
+                    .take(count.max(0) as usize)
+                    .copied()
+                    .collect();
+                cells.sort_unstable_by_key(|c| (c.1, c.0));
+                cells
+            }
+        }
+    }
+
+    /// Picks `count` cells of a map's spawn zone: the middle of it for `Classic`, anywhere
+    /// in it otherwise.
+    fn pick_in_zone(&self, zone: &[(i32, i32)], count: i32, rng: &mut StdRng) -> Vec<(i32, i32)> {
+        let count = (count.max(0) as usize).min(zone.len());
+        let mut cells: Vec<(i32, i32)> = match self {
+            Setup::Classic => {
+                let offset = (zone.len() - count) / 2;
+                zone[offset..offset + count].to_vec()
+            }
+            _ => {
+                let mut cells = zone.to_vec();
+                cells.shuffle(rng);
+                cells.truncate(count);
+                cells
+            }
+        };
+        cells.sort_unstable_by_key(|c| (c.1, c.0));
+        cells
+    }
+
+    /// Where player `p`'s copy of a pattern cell goes, with `seated` players on the board.
+    fn place(&self, cols: i32, rows: i32, p: i32, seated: i32, cell: (i32, i32)) -> (i32, i32) {
+        match self {
+            Setup::Classic | Setup::Mirrored => match p {
+                0 => cell,
+                _ => (cols - 1 - cell.0, cell.1),
+            },
+            Setup::Scattered => match p {
+                0 => cell,
+                _ => (cols - 1 - cell.0, rows - 1 - cell.1),
+            },
+            Setup::Rotational => {
+                // quarter turns clockwise about the centre: left edge, top, right, bottom,
+                // spread evenly so two players still face each other
+                let turns = if 4 % seated == 0 { p * 4 / seated } else { p };
+                // a cell keeps its depth from the edge, and its place along the edge is
+                // stretched to the new edge's length, which only differs off the square
+                let (depth, along) = cell;
+                let across = if rows > 1 {
+                    (along * (cols - 1) + (rows - 1) / 2) / (rows - 1)
+                } else {
+                    0
+                };
+                match turns % 4 {
+                    0 => cell,
+                    1 => (cols - 1 - across, depth),
+                    2 => (cols - 1 - depth, rows - 1 - along),
+                    _ => (across, rows - 1 - depth),
+                }
+            }
+        }
+    }
+}
+
+/// Everything about how a match starts that is chosen before it begins.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchOptions {
+    pub setup: Setup,
+    pub seed: u64,
+    /// Per-player token counts and cooldowns, indexed by player; missing players get the
+    /// defaults.
+    pub handicaps: Vec<Handicap>,
+    /// Per-player token classes in placement order, indexed by player. A player's army, when
+    /// not empty, replaces their handicap's token count; missing players field all pawns.
+    pub armies: Vec<Vec<TokenClass>>,
+    /// `None` for the open square board.
+    pub map: Option<Map>,
+    pub mode: GameMode,
+    /// Seconds of play after which a match nobody has won goes to the tiebreak; `None` plays
+    /// on until someone wins.
+    pub time_limit: Option<f64>,
+    /// Seconds without any capture being possible after which the match is a draw.
+    pub draw_after: Option<f64>,
+    /// When and how fast the board closes in; `None` keeps the whole board open.
+    pub sudden_death: Option<SuddenDeath>,
+    /// Whether a token reaching the edge opposite its start is promoted.
+    pub promotion: bool,
+}
+
+impl MatchOptions {
+    /// The starting board for `np` players, playing by the mode's rules.
+    pub fn board(&self, np: i32) -> Board {
+        let open = Map::default();
+        let map = self.map.as_ref().unwrap_or(&open);
+        let mut board = Board::generate_on(
+            map,
+            np,
+            self.setup,
+            self.seed,
+            &self.handicaps,
+            &self.armies,
+        );
+        board.set_rules(self.mode.rules(&board));
+        board.set_sudden_death(self.sudden_death);
+        board.set_promotion(self.promotion);
+        board
+    }
+}
+
+/// The free floor cell closest to `loc`, which is `loc` itself when it is free.
+fn nearest_open(board: &Board, loc: (i32, i32)) -> Option<(i32, i32)> {
+    (0..board.rows)
+        .flat_map(|row| (0..board.cols).map(move |col| (col, row)))
+        .filter(|cell| board.cell(*cell) != Cell::Wall && board.token_at(*cell).is_none())
+        .min_by_key(|cell| (cell.0 - loc.0).pow(2) + (cell.1 - loc.1).pow(2))
+}
+
+impl Board {
+    /// A fresh open board laid out by `setup`, with every player on the default token count
+    /// and cooldown, all pawns. Only `Rotational` seats more than two players.
+    pub fn generate(np: i32, setup: Setup, seed: u64) -> Board {
+        Board::generate_on(&Map::default(), np, setup, seed, &[], &[])
+    }
+
+    /// A fresh board on `map`. Players with a spawn zone on the map start inside it, the
+    /// rest are placed by `setup`; either way tokens never start on walls.
+    pub fn generate_on(
+        map: &Map,
+        np: i32,
+        setup: Setup,
+        seed: u64,
+        handicaps: &[Handicap],
+        armies: &[Vec<TokenClass>],
+    ) -> Board {
+        let mut board = Board::on_map(map, np);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let candidates = setup.candidates(board.cols, board.rows, &mut rng);
+        let seated = if setup == Setup::Rotational {
+            np.min(4)
+        } else {
+            np.min(2)
+        };
+        let seated = (0..np)
+            .take_while(|p| *p < seated || !map.spawn_zone(*p).is_empty())
+            .count() as i32;
+        // ids are handed out player by player, so each player's tokens are numbered together
+        let mut next_id = 0;
+        for p in 0..seated {
+            let handicap = handicaps.get(p as usize).copied().unwrap_or_default();
+            let army = armies.get(p as usize).map_or(&[][..], |a| &a[..]);
+            let count = if army.is_empty() {
+                handicap.tokens
+            } else {
+                army.len() as i32
+            };
+            let zone = map.spawn_zone(p);
+            let cells: Vec<(i32, i32)> = if zone.is_empty() {
+                setup
+                    .pattern(board.rows, count, &candidates)
+                    .into_iter()
+                    .map(|cell| setup.place(board.cols, board.rows, p, seated, cell))
+                    .collect()
+            } else {
+                setup.pick_in_zone(zone, count, &mut rng)
+            };
+            for (j, loc) in cells.into_iter().enumerate() {
+                let mut token =
+                    Token::new(TokenId(next_id), p, player_constants::COLORS[p as usize]);
+                token.set_cooldown(handicap.cooldown);
+                token.set_class(army.get(j).copied().unwrap_or_default());
+                // a map can wall over a cell the setup picked; fall back to the closest free one
+                let placed = match nearest_open(&board, loc) {
+                    Some(open) => board.place_token(open, token),
+                    None => false,
+                };
+                if placed {
+                    next_id += 1;
+                }
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walled_setup_cells_move_to_the_nearest_open_cell() {
+        let mut grid = String::from("grid\n");
+        for _ in 0..8 {
+            grid += "X......X\n";
+        }
+        let map = Map::parse(&grid).unwrap();
+        for setup in &Setup::ALL {
+            let board = Board::generate_on(&map, 2, *setup, 7, &[], &[]);
+            for p in 0..2 {
+                assert_eq!(board.tokens_remaining(p), MAX_TOKENS, "{:?}", setup);
+            }
+            for (loc, _) in board.tokens() {
+                assert!(
+                    board.cell(loc) != Cell::Wall,
+                    "{:?} placed on {:?}",
+                    setup,
+                    loc
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rotational_turns_a_square_board_a_quarter_at_a_time() {
+        let n = 10;
+        for col in 0..HOME_DEPTH {
+            for row in 0..n {
+                let mut turned = (col, row);
+                for p in 0..4 {
+                    assert_eq!(Setup::Rotational.place(n, n, p, 4, (col, row)), turned);
+                    turned = (n - 1 - turned.1, turned.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotational_seats_four_players_on_their_own_edges_off_the_square() {
+        for (cols, rows) in &[(14, 8), (8, 14)] {
+            let map = Map::open(*cols, *rows);
+            let board = Board::generate_on(&map, 4, Setup::Rotational, 3, &[], &[]);
+            for p in 0..4 {
+                assert_eq!(board.tokens_remaining(p), MAX_TOKENS, "{}x{}", cols, rows);
+            }
+            for (loc, t) in board.tokens() {
+                let depth = match t.owner() {
+                    0 => loc.0,
+                    1 => loc.1,
+                    2 => cols - 1 - loc.0,
+                    _ => rows - 1 - loc.1,
+                };
+                assert!(depth < HOME_DEPTH, "{}x{}: {:?}", cols, rows, loc);
+            }
+        }
+    }
+}
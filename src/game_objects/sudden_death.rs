@@ -0,0 +1,82 @@
+const DEFAULT_EVERY: f64 = 20.0;
+const DEFAULT_WARNING: f64 = 5.0;
+
+/// A late phase that forces the players together: from `start` seconds in, the outermost
+/// ring of the board that is still open turns to wall every `every` seconds, killing any
+/// token standing on it, until only the middle two rows or columns remain. Each ring is
+/// marked `warning` seconds before it goes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SuddenDeath {
+    pub start: f64,
+    pub every: f64,
+    pub warning: f64,
+}
+
+impl SuddenDeath {
+    pub fn new(start: f64) -> SuddenDeath {
+        SuddenDeath {
+            start,
+            every: DEFAULT_EVERY,
+            warning: DEFAULT_WARNING,
+        }
+    }
+
+    /// Reads `<start>[:<every>[:<warning>]]` in seconds, e.g. `120:20:5`.
+    pub fn parse(text: &str) -> Result<SuddenDeath, String> {
+        let bad = || {
+            format!(
+                "cannot read sudden death '{}', expected <start>[:<every>[:<warning>]]",
+                text
+            )
+        };
+        let mut parts = text.split(':').map(|s| s.parse::<f64>().ok());
+        let start = parts
+            .next()
+            .flatten()
+            .filter(|s| *s >= 0.0)
+            .ok_or_else(bad)?;
+        let mut out = SuddenDeath::new(start);
+        if let Some(every) = parts.next() {
+            out.every = every.filter(|e| *e > 0.0).ok_or_else(bad)?;
+        }
+        if let Some(warning) = parts.next() {
+            out.warning = warning.filter(|w| *w >= 0.0).ok_or_else(bad)?;
+        }
+        if parts.next().is_some() {
+            return Err(bad());
+        }
+        Ok(out)
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("{}:{}:{}", self.start, self.every, self.warning)
+    }
+
+    /// When ring `ring` collapses, counting the outermost as 0.
+    pub fn collapse_time(&self, ring: i32) -> f64 {
+        self.start + ring as f64 * self.every
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fills_in_defaults_and_round_trips() {
+        let sd = SuddenDeath::parse("120").unwrap();
+        assert_eq!(sd, SuddenDeath::new(120.0));
+        let sd = SuddenDeath::parse("90:15:3").unwrap();
+        assert_eq!((sd.start, sd.every, sd.warning), (90.0, 15.0, 3.0));
+        assert_eq!(SuddenDeath::parse(&sd.to_text()), Ok(sd));
+        assert_eq!(sd.collapse_time(0), 90.0);
+        assert_eq!(sd.collapse_time(2), 120.0);
+    }
+
+    #[test]
+    fn parse_rejects_bad_times() {
+        for text in &["", "x", "-1", "60:0", "60:-5", "60:10:-1", "60:10:5:1"] {
+            assert!(SuddenDeath::parse(text).is_err(), "accepted {:?}", text);
+        }
+    }
+}
@@ -1,48 +1,51 @@
 use std::f64::consts;
 
-use piston_window::{CircleArc, Context, Ellipse, Graphics};
+use piston_window::{ellipse, CircleArc, Context, Ellipse, Graphics};
 
 use crate::drawing::color;
-use crate::drawing::screen;
 use crate::drawing::token;
 
-use crate::game_objects::TokenStates;
-
-const SCREEN_WIDTH: i64 = screen::WIDTH;
-const SCREEN_HEIGHT: i64 = screen::HEIGHT;
-const BOARD_SIZE: i32 = screen::SIZE;
-
-const CELL_HEIGHT: f64 = (SCREEN_HEIGHT / BOARD_SIZE as i64) as f64;
-const CELL_WIDTH: f64 = (SCREEN_WIDTH / BOARD_SIZE as i64) as f64;
-
-const TOKEN_SIZE: i32 = ((0.6 * (CELL_HEIGHT + CELL_WIDTH)) as i32 / 2) as i32;
+use crate::game_objects::{TokenClass, TokenStates};
 
 const ARC_RESOLUTION: u32 = token::ARC_RESOLUTION;
 const TIMEOUT: f64 = 10.0;
 const INITIAL_WAIT: f64 = 1.0;
+/// Multiplies a promoted token's Wait and Prep times.
+const PROMOTED_COOLDOWN: f64 = 0.75;
+/// Diameter of the promotion mark as a fraction of the token's.
+const PROMOTION_MARK_SIZE: f64 = 0.3;
 
-pub fn cell_pos_to_canvas_pos(pos: (i32, i32)) -> (i32, i32) {
-    let mut x = pos.0 as i64 * SCREEN_WIDTH / BOARD_SIZE as i64;
-    let mut y = pos.1 as i64 * SCREEN_HEIGHT / BOARD_SIZE as i64;
-    x += ((CELL_WIDTH - (TOKEN_SIZE as f64)) / 2.0) as i64;
-    y += ((CELL_HEIGHT - (TOKEN_SIZE as f64)) / 2.0) as i64;
-    (x as i32, y as i32)
-}
+/// Identifies a token for its whole life, independent of the cell it currently occupies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TokenId(pub u32);
 
+#[derive(Clone)]
 pub struct Token {
+    id: TokenId,
+    owner: i32,
     time: f64,
     color: [f32; 4],
     pub state: TokenStates,
     pub wait_time: f64,
+    cooldown: f64,
+    class: TokenClass,
+    flag: Option<i32>,
+    promoted: bool,
 }
 
 impl Token {
-    pub fn new(color: [f32; 4]) -> Token {
+    pub fn new(id: TokenId, owner: i32, color: [f32; 4]) -> Token {
         Token {
+            id,
+            owner,
             time: 0.0,
             color,
             state: TokenStates::Wait,
             wait_time: INITIAL_WAIT,
+            cooldown: 1.0,
+            class: TokenClass::Pawn,
+            flag: None,
+            promoted: false,
         }
     }
 
@@ -51,15 +54,112 @@ impl Token {
         self.color = c;
     }
 
+    pub fn id(&self) -> TokenId {
+        self.id
+    }
+
+    pub fn owner(&self) -> i32 {
+        self.owner
+    }
+
+    pub fn set_owner(&mut self, p: i32) {
+        self.owner = p;
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+
     pub fn set_wait_time(&mut self, t: f64) {
         self.wait_time = t;
     }
 
-    #[allow(unused)]
     pub fn set_state(&mut self, s: TokenStates) {
         self.state = s;
     }
 
+    /// Scales how long the token spends in Wait and Prep; above 1.0 is a handicap.
+    pub fn cooldown(&self) -> f64 {
+        self.cooldown
+    }
+
+    pub fn set_cooldown(&mut self, c: f64) {
+        self.cooldown = c;
+    }
+
+    pub fn class(&self) -> TokenClass {
+        self.class
+    }
+
+    pub fn set_class(&mut self, class: TokenClass) {
+        self.class = class;
+    }
+
+    /// The player whose flag the token is carrying, in capture-the-flag.
+    pub fn flag(&self) -> Option<i32> {
+        self.flag
+    }
+
+    pub fn set_flag(&mut self, flag: Option<i32>) {
+        self.flag = flag;
+    }
+
+    /// Whether the token has reached the far edge, which lets it capture orthogonally as well
+    /// and recover faster.
+    pub fn promoted(&self) -> bool {
+        self.promoted
+    }
+
+    pub fn set_promoted(&mut self, promoted: bool) {
+        self.promoted = promoted;
+    }
+
+    /// Offsets the token may capture at, given its class and promotion.
+    pub fn captures(&self) -> &'static [(i32, i32)] {
+        if self.promoted {
+            self.class.promoted_captures()
+        } else {
+            self.class.captures()
+        }
+    }
+
+    fn recovery(&self) -> f64 {
+        let promoted = if self.promoted {
+            PROMOTED_COOLDOWN
+        } else {
+            1.0
+        };
+        self.cooldown * self.class.cooldown() * promoted
+    }
+
+    fn wait_limit(&self) -> f64 {
+        self.wait_time * self.recovery()
+    }
+
+    fn prep_limit(&self) -> f64 {
+        TIMEOUT * self.recovery()
+    }
+
+    /// Seconds until the token next changes state.
+    pub fn time_left(&self) -> f64 {
+        let total = match self.state {
+            TokenStates::Wait => self.wait_limit(),
+            TokenStates::Prep => self.prep_limit(),
+            TokenStates::Ready => TIMEOUT,
+            TokenStates::Dead => return 0.0,
+        };
+        (total - self.time).max(0.0)
+    }
+
+    /// Seconds spent in the current state.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, t: f64) {
+        self.time = t;
+    }
+
     pub fn update(&mut self, dt: f64) {
         self.time += dt;
         // if in wait state
@@ -67,13 +167,13 @@ impl Token {
             TokenStates::Wait =>
             //see if initial wait has passed
             {
-                if self.time >= self.wait_time {
+                if self.time >= self.wait_limit() {
                     self.reset_time();
                     self.state = TokenStates::Prep;
                 }
             }
             TokenStates::Prep => {
-                if self.time >= TIMEOUT {
+                if self.time >= self.prep_limit() {
                     self.reset_time();
                     self.state = TokenStates::Ready;
                 }
@@ -88,74 +188,84 @@ impl Token {
         }
     }
 
-    pub fn draw_at<G: Graphics>(&self, c: &Context, g: &mut G, pos: (i32, i32)) {
-        let canv_pos = cell_pos_to_canvas_pos(pos);
+    /// Draws the token's shape, its state arc and any promotion mark inside `rect`, which need
+    /// not be aligned to a cell.
+    pub fn draw_in<G: Graphics>(&self, c: &Context, g: &mut G, rect: [f64; 4]) {
+        self.draw_state_arc_in(c, g, rect);
+        self.class.draw_body(self.color, rect, c, g);
+        self.draw_promotion_mark_in(c, g, rect);
+    }
+
+    /// Where the gold dot marking a promoted token goes inside `rect`; `None` if it has not
+    /// been promoted.
+    pub fn promotion_mark(&self, rect: [f64; 4]) -> Option<[f64; 4]> {
+        if !self.promoted {
+            return None;
+        }
+        let [x, y, w, h] = rect;
+        let (mw, mh) = (w * PROMOTION_MARK_SIZE, h * PROMOTION_MARK_SIZE);
+        Some([x + (w - mw) / 2.0, y + (h - mh) / 2.0, mw, mh])
+    }
+
+    pub fn draw_promotion_mark_in<G: Graphics>(&self, c: &Context, g: &mut G, rect: [f64; 4]) {
+        if let Some(mark) = self.promotion_mark(rect) {
+            Ellipse::new(color::GOLD)
+                .border(ellipse::Border {
+                    color: color::BLACK,
+                    radius: 1.0,
+                })
+                .resolution(ARC_RESOLUTION)
+                .draw(mark, &c.draw_state, c.transform, g);
+        }
+    }
+
+    /// The ring showing the token's state and timer as (color, border radius, end angle);
+    /// the arc runs clockwise from angle 0. Dead tokens have none.
+    pub fn state_arc(&self) -> Option<([f32; 4], f64, f64)> {
         match self.state {
-            TokenStates::Prep => CircleArc::new(
+            TokenStates::Prep => Some((
                 color::YELLOW,
                 2.0,
-                0.0,
-                1.9999 * consts::PI * self.time / TIMEOUT,
-            )
-            .resolution(ARC_RESOLUTION)
-            .draw(
-                [
-                    canv_pos.0 as f64,
-                    canv_pos.1 as f64,
-                    TOKEN_SIZE as f64,
-                    TOKEN_SIZE as f64,
-                ],
-                &c.draw_state,
-                c.transform,
-                g,
-            ),
-            TokenStates::Wait => CircleArc::new(
+                1.9999 * consts::PI * self.time / self.prep_limit(),
+            )),
+            TokenStates::Wait => Some((
                 color::RED,
                 2.0,
-                0.0,
-                1.9999 * consts::PI - (1.9999 * consts::PI * self.time / self.wait_time),
-            )
-            .resolution(ARC_RESOLUTION)
-            .draw(
-                [
-                    canv_pos.0 as f64,
-                    canv_pos.1 as f64,
-                    TOKEN_SIZE as f64,
-                    TOKEN_SIZE as f64,
-                ],
-                &c.draw_state,
-                c.transform,
-                g,
-            ),
-            TokenStates::Ready => CircleArc::new(color::BRIGHTGREEN, 4.0, 0.0, 1.9999 * consts::PI)
+                1.9999 * consts::PI - (1.9999 * consts::PI * self.time / self.wait_limit()),
+            )),
+            TokenStates::Ready => Some((color::BRIGHTGREEN, 4.0, 1.9999 * consts::PI)),
+            TokenStates::Dead => None,
+        }
+    }
+
+    /// Draws only the arc showing the token's state and timer, which changes every frame.
+    pub fn draw_state_arc_in<G: Graphics>(&self, c: &Context, g: &mut G, rect: [f64; 4]) {
+        if let Some((arc_color, radius, end)) = self.state_arc() {
+            CircleArc::new(arc_color, radius, 0.0, end)
                 .resolution(ARC_RESOLUTION)
-                .draw(
-                    [
-                        canv_pos.0 as f64,
-                        canv_pos.1 as f64,
-                        TOKEN_SIZE as f64,
-                        TOKEN_SIZE as f64,
-                    ],
-                    &c.draw_state,
-                    c.transform,
-                    g,
-                ),
-            TokenStates::Dead => {}
+                .draw(rect, &c.draw_state, c.transform, g);
         }
-        Ellipse::new(self.color).resolution(ARC_RESOLUTION).draw(
-            [
-                canv_pos.0 as f64,
-                canv_pos.1 as f64,
-                TOKEN_SIZE as f64,
-                TOKEN_SIZE as f64,
-            ],
-            &c.draw_state,
-            c.transform,
-            g,
-        );
     }
 
     pub fn reset_time(&mut self) {
         self.time = 0.0;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn promoted_tokens_recover_faster() {
+        let mut plain = Token::new(TokenId(0), 0, color::WHITE);
+        let mut promoted = plain.clone();
+        promoted.set_promoted(true);
+        for t in [&mut plain, &mut promoted] {
+            t.set_wait_time(4.0);
+            t.update(4.0 * PROMOTED_COOLDOWN + 0.1);
+        }
+        assert!(plain.state == TokenStates::Wait);
+        assert!(promoted.state == TokenStates::Prep);
+    }
+}
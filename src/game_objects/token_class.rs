@@ -0,0 +1,322 @@
+use piston_window::{Context, Ellipse, Graphics, Polygon};
+
+use crate::drawing::token;
+
+const ARC_RESOLUTION: u32 = token::ARC_RESOLUTION;
+
+const ORTHOGONAL: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const DIAGONAL: [(i32, i32); 4] = [(1, -1), (1, 1), (-1, 1), (-1, -1)];
+const RUNNER_STEPS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -2),
+    (2, 0),
+    (0, 2),
+    (-2, 0),
+];
+const KNIGHT: [(i32, i32); 8] = [
+    (1, -2),
+    (2, -1),
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+];
+const KNIGHT_AND_ORTHOGONAL: [(i32, i32); 12] = [
+    (1, -2),
+    (2, -1),
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+];
+const KING: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// What kind of piece a token is, which decides how it moves and captures and how long it
+/// takes to get ready again.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    /// Steps one cell orthogonally, captures one cell diagonally.
+    #[default]
+    Pawn,
+    /// Steps one or two cells orthogonally, the cell passed over must be empty; captures like
+    /// a pawn. Recovers more slowly.
+    Runner,
+    /// Moves and captures a knight's move away, jumping over anything in between. Recovers
+    /// slowest.
+    Jumper,
+    /// Steps one cell in any direction but captures only orthogonally.
+    Guard,
+}
+
+impl TokenClass {
+    pub const ALL: [TokenClass; 4] = [
+        TokenClass::Pawn,
+        TokenClass::Runner,
+        TokenClass::Jumper,
+        TokenClass::Guard,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenClass::Pawn => "pawn",
+            TokenClass::Runner => "runner",
+            TokenClass::Jumper => "jumper",
+            TokenClass::Guard => "guard",
+        }
+    }
+
+    /// The letter used for the class in army specs and position text.
+    pub fn letter(self) -> char {
+        match self {
+            TokenClass::Pawn => 'P',
+            TokenClass::Runner => 'R',
+            TokenClass::Jumper => 'J',
+            TokenClass::Guard => 'G',
+        }
+    }
+
+    pub fn from_letter(c: char) -> Option<TokenClass> {
+        TokenClass::ALL
+            .iter()
+            .find(|class| class.letter() == c.to_ascii_uppercase())
+            .copied()
+    }
+
+    /// Reads an army, one letter per token in placement order, e.g. `PPRJ`.
+    pub fn parse_army(text: &str) -> Result<Vec<TokenClass>, String> {
+        text.chars()
+            .map(|c| {
+                TokenClass::from_letter(c).ok_or_else(|| {
+                    format!(
+                        "unknown token class '{}' in army '{}', expected P, R, J or G",
+                        c, text
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn army_text(army: &[TokenClass]) -> String {
+        army.iter().map(|class| class.letter()).collect()
+    }
+
+    /// Offsets the class may move to when the destination is empty.
+    pub fn steps(self) -> &'static [(i32, i32)] {
+        match self {
+            TokenClass::Pawn => &ORTHOGONAL,
+            TokenClass::Runner => &RUNNER_STEPS,
+            TokenClass::Jumper => &KNIGHT,
+            TokenClass::Guard => &KING,
+        }
+    }
+
+    /// Offsets the class may capture an enemy at.
+    pub fn captures(self) -> &'static [(i32, i32)] {
+        match self {
+            TokenClass::Pawn | TokenClass::Runner => &DIAGONAL,
+            TokenClass::Jumper => &KNIGHT,
+            TokenClass::Guard => &ORTHOGONAL,
+        }
+    }
+
+    /// Offsets a promoted token of the class may capture at: its own captures plus the four
+    /// orthogonal neighbours.
+    pub fn promoted_captures(self) -> &'static [(i32, i32)] {
+        match self {
+            TokenClass::Pawn | TokenClass::Runner => &KING,
+            TokenClass::Jumper => &KNIGHT_AND_ORTHOGONAL,
+            TokenClass::Guard => &ORTHOGONAL,
+        }
+    }
+
+    /// Whether cells between the start and a straight-line destination may be occupied.
+    pub fn leaps(self) -> bool {
+        self == TokenClass::Jumper
+    }
+
+    /// Multiplies Wait and Prep times, on top of any handicap.
+    pub fn cooldown(self) -> f64 {
+        match self {
+            TokenClass::Pawn | TokenClass::Guard => 1.0,
+            TokenClass::Runner => 1.25,
+            TokenClass::Jumper => 1.5,
+        }
+    }
+
+    /// The corners of the class's shape inside `rect`, or `None` for the round pawn.
+    pub fn outline(self, rect: [f64; 4]) -> Option<Vec<[f64; 2]>> {
+        let [x, y, w, h] = rect;
+        let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+        match self {
+            TokenClass::Pawn => None,
+            TokenClass::Runner => Some(vec![[cx, y], [x + w, cy], [cx, y + h], [x, cy]]),
+            // inscribed in the circle, like the other shapes, so it stays inside the ring
+            TokenClass::Jumper => Some(vec![
+                [cx, y],
+                [cx + w * 0.433, cy + h * 0.25],
+                [cx - w * 0.433, cy + h * 0.25],
+            ]),
+            TokenClass::Guard => {
+                let inset = w * 0.146;
+                Some(vec![
+                    [x + inset, y + inset],
+                    [x + w - inset, y + inset],
+                    [x + w - inset, y + h - inset],
+                    [x + inset, y + h - inset],
+                ])
+            }
+        }
+    }
+
+    /// Fills the class's shape in `color` inside `rect`.
+    pub fn draw_body<G: Graphics>(self, color: [f32; 4], rect: [f64; 4], c: &Context, g: &mut G) {
+        match self.outline(rect) {
+            Some(points) => Polygon::new(color).draw(&points, &c.draw_state, c.transform, g),
+            None => Ellipse::new(color).resolution(ARC_RESOLUTION).draw(
+                rect,
+                &c.draw_state,
+                c.transform,
+                g,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{Board, Position, Token, TokenId, TokenStates};
+
+    /// Offsets from the middle of a 5x5 board that `board` lets the token there move to.
+    fn offsets(board: &Board) -> Vec<(i32, i32)> {
+        let mut out: Vec<(i32, i32)> = board
+            .legal_targets(0, (2, 2))
+            .into_iter()
+            .map(|to| (to.0 - 2, to.1 - 2))
+            .collect();
+        out.sort();
+        out
+    }
+
+    fn sorted(offsets: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let mut out = offsets.to_vec();
+        out.sort();
+        out
+    }
+
+    /// A 5x5 board with a Ready token of `class` for player 0 in the middle and, if `filled`,
+    /// an enemy on every other cell.
+    fn centred(class: TokenClass, filled: bool) -> Board {
+        let enemy = if filled { "1r" } else { "." };
+        let mut rows = vec![vec![enemy; 5]; 5];
+        let mover = match class {
+            TokenClass::Pawn => String::from("0r"),
+            _ => format!("0r{}", class.letter()),
+        };
+        rows[2][2] = &mover;
+        let text: Vec<String> = rows.iter().map(|r| r.join(" ")).collect();
+        let board = Position::parse(&text.join("\n")).unwrap().board;
+        assert_eq!(board.token_at((2, 2)).map(|t| t.class()), Some(class));
+        board
+    }
+
+    #[test]
+    fn each_class_steps_its_own_way() {
+        for class in TokenClass::ALL.iter() {
+            assert_eq!(
+                offsets(&centred(*class, false)),
+                sorted(class.steps()),
+                "{}",
+                class.name()
+            );
+        }
+    }
+
+    #[test]
+    fn each_class_captures_its_own_way() {
+        for class in TokenClass::ALL.iter() {
+            // with every cell taken, only captures remain, and only leapers get past the
+            // ring of enemies around the middle
+            let board = centred(*class, true);
+            let reachable: Vec<(i32, i32)> = class
+                .captures()
+                .iter()
+                .copied()
+                .filter(|d| class.leaps() || d.0.abs().max(d.1.abs()) == 1)
+                .collect();
+            assert_eq!(offsets(&board), sorted(&reachable), "{}", class.name());
+        }
+    }
+
+    #[test]
+    fn runners_need_a_clear_path_and_jumpers_do_not() {
+        let board = Position::parse("0rR 1r .\n. . .\n0rJ . .").unwrap().board;
+        assert!(!board.is_legal(0, (0, 0), (2, 0)));
+        assert!(board.is_legal(0, (0, 2), (1, 0)));
+        let board = Position::parse("0rR . .\n. . .\n. . 1r").unwrap().board;
+        assert!(board.is_legal(0, (0, 0), (2, 0)));
+    }
+
+    #[test]
+    fn heavier_classes_recover_more_slowly() {
+        let mut tokens: Vec<Token> = TokenClass::ALL
+            .iter()
+            .map(|class| {
+                let mut t = Token::new(TokenId(0), 0, [1.0; 4]);
+                t.set_class(*class);
+                t.set_wait_time(4.0);
+                t
+            })
+            .collect();
+        let waiting = |tokens: &[Token]| -> Vec<bool> {
+            tokens
+                .iter()
+                .map(|t| t.state == TokenStates::Wait)
+                .collect()
+        };
+        // pawn, runner, jumper, guard
+        tokens.iter_mut().for_each(|t| t.update(4.1));
+        assert_eq!(waiting(&tokens), vec![false, true, true, false]);
+        tokens.iter_mut().for_each(|t| t.update(1.0));
+        assert_eq!(waiting(&tokens), vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn armies_parse_by_letter() {
+        assert_eq!(
+            TokenClass::parse_army("PRjg"),
+            Ok(vec![
+                TokenClass::Pawn,
+                TokenClass::Runner,
+                TokenClass::Jumper,
+                TokenClass::Guard
+            ])
+        );
+        assert!(TokenClass::parse_army("PQ").is_err());
+        let army = TokenClass::ALL.to_vec();
+        assert_eq!(
+            TokenClass::parse_army(&TokenClass::army_text(&army)),
+            Ok(army)
+        );
+    }
+}
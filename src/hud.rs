@@ -0,0 +1,142 @@
+use conrod_core::{widget, widget_ids, Colorable, Positionable, Sizeable, Widget};
+
+use pawn_fight::drawing::screen;
+use pawn_fight::game::Game;
+
+const HUD_HEIGHT: f64 = screen::HUD_HEIGHT as f64;
+const ROW_HEIGHT: f64 = 20.0;
+const SWATCH_SIZE: f64 = 14.0;
+const PAD: f64 = 12.0;
+const BANNER_HEIGHT: f64 = 64.0;
+
+widget_ids! {
+    pub struct HudIds {
+        band,
+        clock,
+        mode,
+        collapse,
+        announcement,
+        banner,
+        banner_text,
+        swatches[],
+        labels[],
+        feed[],
+    }
+}
+
+fn to_conrod_color(c: [f32; 4]) -> conrod_core::Color {
+    conrod_core::color::rgba(c[0], c[1], c[2], c[3])
+}
+
+fn format_clock(seconds: f64) -> String {
+    let total = seconds as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// Sets the in-match HUD widgets: a band across the top of the window holding each player's
+/// color, name, living tokens and kills, the match clock with the game mode's progress under
+/// it and any sudden death countdown, and a feed of the latest kills. Promotions are
+/// announced just below the band for a few seconds, and once the match is won a banner
+/// across the middle of the window says who won and how.
+pub fn set_widgets(ui: &mut conrod_core::UiCell<'_>, ids: &mut HudIds, game: &Game) {
+    let summaries = game.player_summaries();
+    let feed: Vec<String> = game
+        .kill_feed()
+        .map(|k| {
+            format!(
+                "{}  {} took {}",
+                format_clock(k.time),
+                game.player_name(k.killer),
+                game.player_name(k.victim)
+            )
+        })
+        .collect();
+    ids.swatches
+        .resize(summaries.len(), &mut ui.widget_id_generator());
+    ids.labels
+        .resize(summaries.len(), &mut ui.widget_id_generator());
+    ids.feed.resize(feed.len(), &mut ui.widget_id_generator());
+
+    widget::Canvas::new()
+        .h(HUD_HEIGHT)
+        .mid_top()
+        .color(conrod_core::color::rgb(0.2, 0.35, 0.45))
+        .set(ids.band, ui);
+
+    for (i, summary) in summaries.iter().enumerate() {
+        widget::Rectangle::fill_with([SWATCH_SIZE, SWATCH_SIZE], to_conrod_color(summary.color))
+            .top_left_with_margins_on(ids.band, PAD + ROW_HEIGHT * i as f64 + 3.0, PAD)
+            .set(ids.swatches[i], ui);
+        widget::Text::new(&format!(
+            "{}   tokens: {}   kills: {}",
+            summary.name, summary.tokens, summary.kills
+        ))
+        .font_size(14)
+        .color(conrod_core::color::WHITE)
+        .right_from(ids.swatches[i], 8.0)
+        .align_middle_y_of(ids.swatches[i])
+        .set(ids.labels[i], ui);
+    }
+
+    // counts down when the match has a time limit
+    let clock = game.time_left().unwrap_or_else(|| game.elapsed());
+    widget::Text::new(&format_clock(clock))
+        .font_size(26)
+        .color(conrod_core::color::WHITE)
+        .mid_top_with_margin_on(ids.band, PAD)
+        .set(ids.clock, ui);
+
+    if let Some(status) = game.mode_status() {
+        widget::Text::new(&status)
+            .font_size(12)
+            .color(conrod_core::color::WHITE)
+            .down_from(ids.clock, 6.0)
+            .align_middle_x_of(ids.clock)
+            .set(ids.mode, ui);
+    }
+
+    if let Some(collapse) = game.collapse_status() {
+        // red once the ring about to go is marked on the board
+        let color = if game.board().collapse_warning().is_some() {
+            conrod_core::color::LIGHT_RED
+        } else {
+            conrod_core::color::WHITE
+        };
+        let text = widget::Text::new(&collapse).font_size(12).color(color);
+        let text = if game.mode_status().is_some() {
+            text.down_from(ids.mode, 4.0)
+        } else {
+            text.down_from(ids.clock, 6.0)
+        };
+        text.align_middle_x_of(ids.clock).set(ids.collapse, ui);
+    }
+
+    if let Some(announcement) = game.announcement() {
+        widget::Text::new(&announcement)
+            .font_size(20)
+            .color(conrod_core::color::YELLOW)
+            .mid_top_with_margin_on(ui.window, HUD_HEIGHT + PAD)
+            .set(ids.announcement, ui);
+    }
+
+    if let Some(outcome) = game.outcome_text() {
+        widget::Canvas::new()
+            .h(BANNER_HEIGHT)
+            .middle_of(ui.window)
+            .color(conrod_core::color::rgba(0.0, 0.0, 0.0, 0.75))
+            .set(ids.banner, ui);
+        widget::Text::new(&outcome)
+            .font_size(24)
+            .color(conrod_core::color::WHITE)
+            .middle_of(ids.banner)
+            .set(ids.banner_text, ui);
+    }
+
+    for (i, line) in feed.iter().enumerate() {
+        widget::Text::new(line)
+            .font_size(12)
+            .color(conrod_core::color::LIGHT_GREY)
+            .top_right_with_margins_on(ids.band, PAD + ROW_HEIGHT * i as f64, PAD)
+            .set(ids.feed[i], ui);
+    }
+}
@@ -0,0 +1,10 @@
+#![warn(rust_2018_idioms)]
+
+pub mod ai;
+pub mod drawing;
+pub mod env;
+pub mod game;
+pub mod game_objects;
+pub mod protocol;
+pub mod replay;
+pub mod tournament;
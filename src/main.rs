@@ -2,21 +2,31 @@
 
 use conrod_core::{widget_ids, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget};
 use piston_window::{
-    clear, texture::UpdateTexture, Button, EventLoop, G2d, G2dTexture, MouseCursorEvent, OpenGL,
-    PistonWindow, PressEvent, TextureSettings, UpdateEvent, Window, WindowSettings,
+    clear, texture::UpdateTexture, Button, EventLoop, G2d, G2dTexture, Key, MouseCursorEvent,
+    OpenGL, PistonWindow, PressEvent, ResizeEvent, TextureSettings, UpdateEvent, Window,
+    WindowSettings,
 };
 
-mod drawing;
-mod game;
-mod game_objects;
+mod hud;
 
-use crate::{
-    drawing::{color, screen},
+use pawn_fight::{
+    drawing::{self, color, screen},
     game::Game,
+    game_objects::{
+        ExternalPlayer, GameMode, Handicap, HumanPlayer, InputTypes, Map, MatchOptions, Position,
+        Setup, SuddenDeath, TokenClass,
+    },
+    replay::Replay,
 };
 
 const SCREEN_WIDTH: u32 = screen::WIDTH as u32;
-const SCREEN_HEIGHT: u32 = screen::HEIGHT as u32;
+const SCREEN_HEIGHT: u32 = (screen::HEIGHT + screen::HUD_HEIGHT) as u32;
+
+/// Seats in a window match: the mouse player and the keyboard player.
+const SEATS: i32 = 2;
+
+const GIF_EVERY: u64 = 6;
+const GIF_SIZE: u32 = 384;
 
 widget_ids! {
     struct Ids {
@@ -31,6 +41,12 @@ struct CommandLineArgs {
     is_server: bool,
     is_client: bool,
     remoteip: String,
+    render: Option<(String, String)>,
+    record: Option<String>,
+    gif: Option<(String, String)>,
+    gif_every: u64,
+    bots: Vec<(i32, String)>,
+    options: MatchOptions,
 }
 
 impl CommandLineArgs {
@@ -39,10 +55,23 @@ impl CommandLineArgs {
             is_server: false,
             is_client: false,
             remoteip: String::from(""),
+            render: None,
+            record: None,
+            gif: None,
+            gif_every: GIF_EVERY,
+            bots: Vec::new(),
+            options: MatchOptions::default(),
         }
     }
 }
 
+/// A length of time in seconds, if `text` is a finite number above zero.
+fn parse_seconds(text: &str) -> Option<f64> {
+    text.parse::<f64>()
+        .ok()
+        .filter(|s| s.is_finite() && *s > 0.0)
+}
+
 fn parse_args() -> Result<CommandLineArgs, String> {
     let mut cli_args = CommandLineArgs::new();
     let mut args = std::env::args();
@@ -57,6 +86,98 @@ fn parse_args() -> Result<CommandLineArgs, String> {
                     return Err(String::from("--client requires <remote_ip>"));
                 }
             }
+            "--render" => match (args.next(), args.next()) {
+                (Some(position), Some(png)) => cli_args.render = Some((position, png)),
+                _ => return Err(String::from("--render requires <position.txt> <out.png>")),
+            },
+            "--record" => match args.next() {
+                Some(path) => cli_args.record = Some(path),
+                None => return Err(String::from("--record requires <replay.txt>")),
+            },
+            "--gif" => match (args.next(), args.next()) {
+                (Some(replay), Some(gif)) => cli_args.gif = Some((replay, gif)),
+                _ => return Err(String::from("--gif requires <replay.txt> <out.gif>")),
+            },
+            "--every" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => cli_args.gif_every = n,
+                None => return Err(String::from("--every requires <ticks>")),
+            },
+            "--layout" => match args.next() {
+                Some(name) => cli_args.options.setup = Setup::parse(&name)?,
+                None => return Err(String::from("--layout requires <name>")),
+            },
+            "--mode" => match args.next() {
+                Some(spec) => cli_args.options.mode = GameMode::parse(&spec)?,
+                None => return Err(String::from("--mode requires <name>[:seconds]")),
+            },
+            "--time-limit" => match args.next().and_then(|n| parse_seconds(&n)) {
+                Some(secs) => cli_args.options.time_limit = Some(secs),
+                None => return Err(String::from("--time-limit requires <seconds> above 0")),
+            },
+            "--draw-after" => match args.next().and_then(|n| parse_seconds(&n)) {
+                Some(secs) => cli_args.options.draw_after = Some(secs),
+                None => return Err(String::from("--draw-after requires <seconds> above 0")),
+            },
+            "--promotion" => cli_args.options.promotion = true,
+            "--sudden-death" => match args.next() {
+                Some(spec) => cli_args.options.sudden_death = Some(SuddenDeath::parse(&spec)?),
+                None => {
+                    return Err(String::from(
+                        "--sudden-death requires <start>[:<every>[:<warning>]]",
+                    ))
+                }
+            },
+            "--seed" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => cli_args.options.seed = n,
+                None => return Err(String::from("--seed requires <number>")),
+            },
+            "--handicap" => match (
+                args.next().and_then(|n| n.parse::<usize>().ok()),
+                args.next(),
+            ) {
+                (Some(n), Some(spec)) if (1..=SEATS as usize).contains(&n) => {
+                    let handicaps = &mut cli_args.options.handicaps;
+                    if handicaps.len() < n {
+                        handicaps.resize(n, Handicap::default());
+                    }
+                    handicaps[n - 1] = Handicap::parse(&spec)?;
+                }
+                _ => {
+                    return Err(String::from(
+                        "--handicap requires <player 1|2> <tokens>[x<cooldown>]",
+                    ))
+                }
+            },
+            "--army" => match (
+                args.next().and_then(|n| n.parse::<usize>().ok()),
+                args.next(),
+            ) {
+                (Some(n), Some(spec)) if (1..=SEATS as usize).contains(&n) => {
+                    let armies = &mut cli_args.options.armies;
+                    if armies.len() < n {
+                        armies.resize(n, Vec::new());
+                    }
+                    armies[n - 1] = TokenClass::parse_army(&spec)?;
+                }
+                _ => {
+                    return Err(String::from(
+                        "--army requires <player 1|2> <classes, e.g. PRJG>",
+                    ))
+                }
+            },
+            "--map" => match args.next() {
+                Some(path) => cli_args.options.map = Some(Map::load(&path)?),
+                None => return Err(String::from("--map requires <file.map>")),
+            },
+            "--bot" => match (args.next().and_then(|n| n.parse::<i32>().ok()), args.next()) {
+                (Some(n), Some(command)) if (1..=SEATS).contains(&n) => {
+                    if cli_args.bots.iter().any(|(p, _command)| *p == n - 1) {
+                        return Err(format!("--bot seats player {} twice", n));
+                    }
+                    cli_args.bots.push((n - 1, command));
+                }
+                _ => return Err(String::from("--bot requires <player 1|2> \"<command>\"")),
+            },
             _ => (),
         }
     }
@@ -64,6 +185,40 @@ fn parse_args() -> Result<CommandLineArgs, String> {
     Ok(cli_args)
 }
 
+/// Draws a position file to a PNG without opening a window.
+fn render_to_png(position_path: &str, png_path: &str) -> Result<(), String> {
+    let text = std::fs::read_to_string(position_path)
+        .map_err(|e| format!("could not read {}: {}", position_path, e))?;
+    let position = Position::parse(&text).map_err(|e| format!("{}: {}", position_path, e))?;
+    drawing::save_png(&position, SCREEN_WIDTH, screen::HEIGHT as u32, png_path)
+}
+
+/// Turns a recorded match into an animated GIF without opening a window.
+fn replay_to_gif(replay_path: &str, gif_path: &str, every: u64) -> Result<(), String> {
+    let text = std::fs::read_to_string(replay_path)
+        .map_err(|e| format!("could not read {}: {}", replay_path, e))?;
+    let replay = Replay::parse(&text).map_err(|e| format!("{}: {}", replay_path, e))?;
+    drawing::export_gif(&replay, every, GIF_SIZE, gif_path)
+}
+
+/// The usual mouse and keyboard seats, with any seat given to a bot executable taken out.
+fn new_game(args: &CommandLineArgs) -> Result<Game, String> {
+    let bots = &args.bots;
+    let is_bot = |p: i32| bots.iter().any(|(seat, _command)| *seat == p);
+    let humans = [InputTypes::Mouse, InputTypes::Keyboard]
+        .iter()
+        .enumerate()
+        .map(|(p, input)| (p as i32, *input))
+        .filter(|(p, _input)| !is_bot(*p))
+        .map(|(p, input)| HumanPlayer::new(p, input))
+        .collect();
+    let mut game = Game::with_options(humans, args.options.clone());
+    for (p, command) in bots {
+        game.add_controller(Box::new(ExternalPlayer::spawn(*p, command)?));
+    }
+    Ok(game)
+}
+
 /// A set of reasonable stylistic defaults that works for the `gui` below.
 pub fn theme() -> conrod_core::Theme {
     use conrod_core::position::{Align, Direction, Padding, Position, Relative};
@@ -88,13 +243,37 @@ pub fn theme() -> conrod_core::Theme {
 }
 
 fn main() {
-    let args = parse_args();
-    if args.is_err() {
-        println!("{}", args.unwrap_err());
-        std::process::exit(1);
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some((position_path, png_path)) = args.render.as_ref() {
+        if let Err(e) = render_to_png(position_path, png_path) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
     }
 
-    let mut game = Game::new();
+    if let Some((replay_path, gif_path)) = args.gif.as_ref() {
+        if let Err(e) = replay_to_gif(replay_path, gif_path, args.gif_every) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut game = match new_game(&args) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Construct the window.
     let mut window: PistonWindow =
@@ -102,6 +281,7 @@ fn main() {
             .graphics_api(OpenGL::V3_2)
             .samples(64)
             .exit_on_esc(true)
+            .resizable(true)
             .vsync(true)
             .build()
             .unwrap();
@@ -123,22 +303,25 @@ fn main() {
 
     // Create a texture to use for efficiently caching text on the GPU.
     let mut text_vertex_data = Vec::new();
+    // Sized in pixels so glyphs stay crisp on HiDPI displays.
+    let draw_size = window.draw_size();
+    let (cache_width, cache_height) = (draw_size.width as u32, draw_size.height as u32);
     let (mut glyph_cache, mut text_texture_cache) = {
         const SCALE_TOLERANCE: f32 = 0.1;
         const POSITION_TOLERANCE: f32 = 0.1;
         let cache = conrod_core::text::GlyphCache::builder()
-            .dimensions(SCREEN_WIDTH, SCREEN_HEIGHT)
+            .dimensions(cache_width, cache_height)
             .scale_tolerance(SCALE_TOLERANCE)
             .position_tolerance(POSITION_TOLERANCE)
             .build();
-        let buffer_len = SCREEN_WIDTH as usize * SCREEN_HEIGHT as usize;
+        let buffer_len = cache_width as usize * cache_height as usize;
         let init = vec![128; buffer_len];
         let settings = TextureSettings::new();
         let texture = G2dTexture::from_memory_alpha(
             &mut texture_context,
             &init,
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            cache_width,
+            cache_height,
             &settings,
         )
         .unwrap();
@@ -150,19 +333,24 @@ fn main() {
     let image_map = conrod_core::image::Map::new();
 
     let ids = Ids::new(ui.widget_id_generator());
+    let mut hud_ids = hud::HudIds::new(ui.widget_id_generator());
     let mut show_gui: bool = true;
     let mut clearnow: bool = false;
+    let mut toggle_fullscreen: bool = false;
 
     // Poll events from the window
     while let Some(e) = window.next() {
         e.update(|args| game.update(args.dt));
 
+        e.resize(|args| game.resize(args.window_size));
+
         e.press(|b| {
             match b {
                 Button::Mouse(button) => {
                     // println!("Pressed mouse button '{:?}'", button);
                     game.handle_mouse_click(button, cursor);
                 }
+                Button::Keyboard(Key::F11) => toggle_fullscreen = true,
                 Button::Keyboard(key) => {
                     // println!("Pressed keyboard button '{:?}'", key);
                     game.handle_key_press(key);
@@ -171,6 +359,16 @@ fn main() {
             }
         });
 
+        if toggle_fullscreen {
+            let glutin_window = window.window.ctx.window();
+            if glutin_window.get_fullscreen().is_some() {
+                glutin_window.set_fullscreen(None);
+            } else {
+                glutin_window.set_fullscreen(Some(glutin_window.get_current_monitor()));
+            }
+            toggle_fullscreen = false;
+        }
+
         e.mouse_cursor(|[x, y]| {
             // println!("Mouse moved '{} {}'", x, y);
             cursor = [x, y];
@@ -212,61 +410,73 @@ fn main() {
                     show_gui = false;
                     clearnow = true;
                 }
+            } else {
+                hud::set_widgets(&mut ui.set_widgets(), &mut hud_ids, &game);
             }
         });
 
         window.draw_2d(&e, |context, graphics, device| {
-            if show_gui {
-                if let Some(primitives) = ui.draw_if_changed() {
-                    // A function used for caching glyphs to the texture cache.
-                    let cache_queued_glyphs =
-                        |_graphics: &mut G2d<'_>,
-                         cache: &mut G2dTexture,
-                         rect: conrod_core::text::rt::Rect<u32>,
-                         data: &[u8]| {
-                            let offset = [rect.min.x, rect.min.y];
-                            let size = [rect.width(), rect.height()];
-                            let format = piston_window::texture::Format::Rgba8;
-                            text_vertex_data.clear();
-                            text_vertex_data
-                                .extend(data.iter().flat_map(|&b| vec![255, 255, 255, b]));
-                            UpdateTexture::update(
-                                cache,
-                                &mut texture_context,
-                                format,
-                                &text_vertex_data[..],
-                                offset,
-                                size,
-                            )
-                            .expect("failed to update texture")
-                        };
-
-                    // Specify how to get the drawable texture from the image. In this case, the image
-                    // *is* the texture.
-                    fn texture_from_image<T>(img: &T) -> &T {
-                        img
-                    }
-
-                    conrod_piston::draw::primitives(
-                        primitives,
-                        context,
-                        graphics,
-                        &mut text_texture_cache,
-                        &mut glyph_cache,
-                        &image_map,
-                        cache_queued_glyphs,
-                        texture_from_image,
-                    );
-                }
-
-                texture_context.encoder.flush(device);
-            } else {
-                game.render(&context, graphics);
+            if !show_gui {
+                game.render(&context, graphics, &mut texture_context);
                 if clearnow {
                     clear(color::BLACK, graphics);
                     clearnow = false;
                 }
             }
+            // the menu only redraws when it changes, the HUD is drawn over the board every frame
+            let primitives = if show_gui {
+                ui.draw_if_changed()
+            } else {
+                Some(ui.draw())
+            };
+            if let Some(primitives) = primitives {
+                // A function used for caching glyphs to the texture cache.
+                let cache_queued_glyphs = |_graphics: &mut G2d<'_>,
+                                           cache: &mut G2dTexture,
+                                           rect: conrod_core::text::rt::Rect<u32>,
+                                           data: &[u8]| {
+                    let offset = [rect.min.x, rect.min.y];
+                    let size = [rect.width(), rect.height()];
+                    let format = piston_window::texture::Format::Rgba8;
+                    text_vertex_data.clear();
+                    text_vertex_data.extend(data.iter().flat_map(|&b| vec![255, 255, 255, b]));
+                    UpdateTexture::update(
+                        cache,
+                        &mut texture_context,
+                        format,
+                        &text_vertex_data[..],
+                        offset,
+                        size,
+                    )
+                    .expect("failed to update texture")
+                };
+
+                // Specify how to get the drawable texture from the image. In this case, the image
+                // *is* the texture.
+                fn texture_from_image<T>(img: &T) -> &T {
+                    img
+                }
+
+                conrod_piston::draw::primitives(
+                    primitives,
+                    context,
+                    graphics,
+                    &mut text_texture_cache,
+                    &mut glyph_cache,
+                    &image_map,
+                    cache_queued_glyphs,
+                    texture_from_image,
+                );
+            }
+
+            texture_context.encoder.flush(device);
         });
     }
+
+    if let Some(path) = args.record.as_ref() {
+        if let Err(e) = std::fs::write(path, game.replay().to_text()) {
+            eprintln!("could not write {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
 }
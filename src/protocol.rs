@@ -0,0 +1,173 @@
+//! Line-based text protocol for bots running as separate processes, in the spirit of UCI.
+//!
+//! The game writes to the engine's stdin:
+//!
+//! ```text
+//! pawnfight 1                       # once, protocol version
+//! newgame 10 10 2 1                 # cols rows players and the engine's own player number
+//! position 12.5                     # seconds since the start, followed by one line per token
+//! token 4 1 9 3 ready 6.2 runner    # id owner col row wait|prep|ready seconds-left-in-state
+//!                                   # and pawn|runner|jumper|guard, then `promoted` for a
+//!                                   # token promoted on the far edge
+//! end                               # end of the position
+//! quit                              # the match is over, exit
+//! ```
+//!
+//! Positions are sent several times a second and straight after anything moves, whether or
+//! not the engine has answered the last one. The engine writes to its stdout, at any time:
+//!
+//! ```text
+//! name Randy                        # optional, shown in place of the executable name
+//! move 9 3 8 3                      # from col, from row, to col, to row
+//! ```
+//!
+//! Moves go through the same rules as human moves and illegal ones are dropped, as are moves
+//! still queued when a token moves or dies. Any other line is ignored, so engines may log
+//! freely.
+
+use crate::game_objects::{Board, TokenStates};
+
+pub const VERSION: u32 = 1;
+
+/// Something an engine said.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reply {
+    Name(String),
+    Move((i32, i32), (i32, i32)),
+}
+
+pub fn hello() -> String {
+    format!("pawnfight {}\n", VERSION)
+}
+
+pub fn new_game(board: &Board, you: i32) -> String {
+    format!(
+        "newgame {} {} {} {}\n",
+        board.cols,
+        board.rows,
+        board.num_players(),
+        you
+    )
+}
+
+/// The whole board as a `position` block.
+pub fn position(board: &Board, time: f64) -> String {
+    let mut out = format!("position {:.3}\n", time);
+    for (pos, t) in board.tokens() {
+        let state = match t.state {
+            TokenStates::Wait => "wait",
+            TokenStates::Prep => "prep",
+            TokenStates::Ready => "ready",
+            TokenStates::Dead => continue,
+        };
+        let promoted = if t.promoted() { " promoted" } else { "" };
+        out.push_str(&format!(
+            "token {} {} {} {} {} {:.3} {}{}\n",
+            t.id().0,
+            t.owner(),
+            pos.0,
+            pos.1,
+            state,
+            t.time_left(),
+            t.class().name(),
+            promoted
+        ));
+    }
+    out.push_str("end\n");
+    out
+}
+
+pub fn quit() -> String {
+    String::from("quit\n")
+}
+
+/// Reads one line from an engine; `None` for anything that is not part of the protocol.
+pub fn parse_reply(line: &str) -> Option<Reply> {
+    let line = line.trim();
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "name" => {
+            let name = line["name".len()..].trim();
+            if name.is_empty() {
+                None
+            } else {
+                Some(Reply::Name(name.to_string()))
+            }
+        }
+        "move" => {
+            let nums: Vec<i32> = words.map(|w| w.parse().ok()).collect::<Option<_>>()?;
+            match nums.as_slice() {
+                [fc, fr, tc, tr] => Some(Reply::Move((*fc, *fr), (*tc, *tr))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_objects::{player_constants, Position, Token, TokenId};
+
+    #[test]
+    fn parse_reply_reads_names_and_moves() {
+        assert_eq!(
+            parse_reply("name Deep  Pawn \n"),
+            Some(Reply::Name(String::from("Deep  Pawn")))
+        );
+        assert_eq!(
+            parse_reply("  move 9 3 8 3"),
+            Some(Reply::Move((9, 3), (8, 3)))
+        );
+        assert_eq!(
+            parse_reply("move -1 0 0 -1"),
+            Some(Reply::Move((-1, 0), (0, -1)))
+        );
+    }
+
+    #[test]
+    fn parse_reply_ignores_everything_else() {
+        for line in [
+            "",
+            "   ",
+            "name",
+            "name   ",
+            "names Randy",
+            "move 1 2 3",
+            "move 1 2 3 4 5",
+            "move a b c d",
+            "move 1.5 2 3 4",
+            "info depth 3",
+            "MOVE 1 2 3 4",
+        ] {
+            assert_eq!(parse_reply(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn new_game_names_the_board_and_seat() {
+        let board = Position::parse("0r . . .\n. . . 1r\n. 2r . .")
+            .unwrap()
+            .board;
+        assert_eq!(new_game(&board, 2), "newgame 4 3 3 2\n");
+    }
+
+    #[test]
+    fn position_lists_living_tokens() {
+        let mut board = Position::parse("0r:1 . 1pR+:2.5\n. . 1w:0.5/4")
+            .unwrap()
+            .board;
+        let mut dead = Token::new(TokenId(9), 1, player_constants::COLORS[1]);
+        dead.state = TokenStates::Dead;
+        assert!(board.place_token((1, 1), dead));
+        assert_eq!(
+            position(&board, 12.5),
+            "position 12.500\n\
+             token 0 0 0 0 ready 9.000 pawn\n\
+             token 1 1 2 0 prep 6.875 runner promoted\n\
+             token 2 1 2 1 wait 3.500 pawn\n\
+             end\n"
+        );
+    }
+}
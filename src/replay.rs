@@ -0,0 +1,330 @@
+use crate::game_objects::player_constants;
+use crate::game_objects::{
+    Board, BoardEvent, GameMode, Handicap, Map, MatchOptions, Setup, SuddenDeath, TokenClass,
+};
+
+/// Default simulation step, matching the window's 60 updates per second.
+pub const DEFAULT_TICK: f64 = 1.0 / 60.0;
+
+/// A move some player asked for, and the update tick it was handed to the board on.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedMove {
+    pub tick: u64,
+    pub player: i32,
+    pub from: (i32, i32),
+    pub to: (i32, i32),
+}
+
+/// Everything needed to re-simulate a match: the simulation is deterministic given the
+/// starting board, the tick length and the moves, so only those are kept. Saved as text:
+///
+/// ```text
+/// players 2
+/// layout mirrored 42
+/// mode hill:30
+/// timelimit 300
+/// drawafter 60
+/// suddendeath 120:20:5
+/// promotion
+/// handicap 2 3x1.5
+/// army 1 PRJG
+/// map Pillars
+/// maprow 1..X..X..2
+/// tick 0.016666666666666666
+/// ticks 5400
+/// move 660 0 0 3 1 3
+/// ```
+///
+/// where the optional lines give the `MatchOptions`: the `Setup` and its seed, the `GameMode`, the
+/// match's time limit and stalemate draw in seconds, the `SuddenDeath` start, interval and warning
+/// in seconds, whether tokens are promoted on the far edge, a player's token count and cooldown
+/// multiplier, a player's token classes, and the map's name followed by its grid one row per
+/// `maprow`. Handicap and army lines number players from 1, as the command line does. A move line
+/// is `move <tick> <player> <from col> <from row> <to col> <to row>`, with players from 0.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub num_players: i32,
+    pub options: MatchOptions,
+    pub tick_seconds: f64,
+    pub ticks: u64,
+    pub moves: Vec<RecordedMove>,
+}
+
+impl Replay {
+    pub fn new(num_players: i32, tick_seconds: f64) -> Replay {
+        Replay {
+            num_players,
+            options: MatchOptions::default(),
+            tick_seconds,
+            ticks: 0,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut replay = Replay::new(2, DEFAULT_TICK);
+        let mut map_text: Option<String> = None;
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad = || format!("line {}: cannot read '{}'", n + 1, line);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let num = |i: usize| -> Result<f64, String> {
+                words.get(i).and_then(|w| w.parse().ok()).ok_or_else(bad)
+            };
+            // handicap and army lines count players from 1; the index returned is from 0
+            let seat = |i: usize| -> Result<usize, String> {
+                words
+                    .get(i)
+                    .and_then(|w| w.parse::<usize>().ok())
+                    .filter(|p| (1..=player_constants::COLORS.len()).contains(p))
+                    .map(|p| p - 1)
+                    .ok_or_else(bad)
+            };
+            match words[0] {
+                "players" => {
+                    let max = player_constants::COLORS.len();
+                    replay.num_players = words
+                        .get(1)
+                        .and_then(|w| w.parse::<usize>().ok())
+                        .filter(|np| (2..=max).contains(np))
+                        .ok_or_else(|| format!("line {}: players must be 2 to {}", n + 1, max))?
+                        as i32;
+                }
+                "layout" => {
+                    replay.options.setup = Setup::parse(words.get(1).copied().unwrap_or(""))
+                        .map_err(|e| format!("line {}: {}", n + 1, e))?;
+                    replay.options.seed = words.get(2).and_then(|w| w.parse().ok()).unwrap_or(0);
+                }
+                "mode" => {
+                    replay.options.mode = GameMode::parse(words.get(1).copied().unwrap_or(""))
+                        .map_err(|e| format!("line {}: {}", n + 1, e))?;
+                }
+                "timelimit" => replay.options.time_limit = Some(num(1)?),
+                "drawafter" => replay.options.draw_after = Some(num(1)?),
+                "promotion" => replay.options.promotion = true,
+                "suddendeath" => {
+                    replay.options.sudden_death = Some(
+                        SuddenDeath::parse(words.get(1).copied().unwrap_or(""))
+                            .map_err(|e| format!("line {}: {}", n + 1, e))?,
+                    );
+                }
+                "map" => map_text = Some(format!("name {}\ngrid\n", line["map".len()..].trim())),
+                "maprow" => match map_text.as_mut() {
+                    Some(text) => {
+                        *text += words.get(1).copied().unwrap_or("");
+                        text.push('\n');
+                    }
+                    None => return Err(bad()),
+                },
+                "handicap" => {
+                    let p = seat(1)?;
+                    let handicap = Handicap::parse(words.get(2).copied().unwrap_or(""))
+                        .map_err(|e| format!("line {}: {}", n + 1, e))?;
+                    let handicaps = &mut replay.options.handicaps;
+                    if handicaps.len() <= p {
+                        handicaps.resize(p + 1, Handicap::default());
+                    }
+                    handicaps[p] = handicap;
+                }
+                "army" => {
+                    let p = seat(1)?;
+                    let army = TokenClass::parse_army(words.get(2).copied().unwrap_or(""))
+                        .map_err(|e| format!("line {}: {}", n + 1, e))?;
+                    let armies = &mut replay.options.armies;
+                    if armies.len() <= p {
+                        armies.resize(p + 1, Vec::new());
+                    }
+                    armies[p] = army;
+                }
+                "tick" => replay.tick_seconds = num(1)?,
+                "ticks" => replay.ticks = num(1)? as u64,
+                "move" => replay.moves.push(RecordedMove {
+                    tick: num(1)? as u64,
+                    player: num(2)? as i32,
+                    from: (num(3)? as i32, num(4)? as i32),
+                    to: (num(5)? as i32, num(6)? as i32),
+                }),
+                _ => return Err(bad()),
+            }
+        }
+        if let Some(text) = map_text {
+            replay.options.map = Some(Map::parse(&text).map_err(|e| format!("map: {}", e))?);
+        }
+        let seats = replay
+            .options
+            .handicaps
+            .len()
+            .max(replay.options.armies.len());
+        if seats > replay.num_players as usize {
+            return Err(format!(
+                "player {} has a handicap or army but there are {} players",
+                seats, replay.num_players
+            ));
+        }
+        if replay.tick_seconds <= 0.0 {
+            return Err(String::from("tick length must be positive"));
+        }
+        Ok(replay)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("players {}\n", self.num_players);
+        let options = &self.options;
+        if options.setup != Setup::Classic || options.seed != 0 {
+            out += &format!("layout {} {}\n", options.setup.name(), options.seed);
+        }
+        if options.mode != GameMode::Elimination {
+            out += &format!("mode {}\n", options.mode.to_text());
+        }
+        if let Some(limit) = options.time_limit {
+            out += &format!("timelimit {}\n", limit);
+        }
+        if let Some(after) = options.draw_after {
+            out += &format!("drawafter {}\n", after);
+        }
+        if let Some(sudden_death) = options.sudden_death {
+            out += &format!("suddendeath {}\n", sudden_death.to_text());
+        }
+        if options.promotion {
+            out += "promotion\n";
+        }
+        for (p, handicap) in options.handicaps.iter().enumerate() {
+            if *handicap != Handicap::default() {
+                out += &format!("handicap {} {}\n", p + 1, handicap.to_text());
+            }
+        }
+        for (p, army) in options.armies.iter().enumerate() {
+            if !army.is_empty() {
+                out += &format!("army {} {}\n", p + 1, TokenClass::army_text(army));
+            }
+        }
+        if let Some(map) = options.map.as_ref() {
+            out += &format!("map {}\n", map.name);
+            for row in map.grid_lines() {
+                out += &format!("maprow {}\n", row);
+            }
+        }
+        out += &format!("tick {}\nticks {}\n", self.tick_seconds, self.ticks);
+        for m in self.moves.iter() {
+            out += &format!(
+                "move {} {} {} {} {} {}\n",
+                m.tick, m.player, m.from.0, m.from.1, m.to.0, m.to.1
+            );
+        }
+        out
+    }
+
+    /// Steps through the match tick by tick.
+    pub fn playback(&self) -> Playback<'_> {
+        Playback {
+            replay: self,
+            board: self.options.board(self.num_players),
+            tick: 0,
+            next_move: 0,
+        }
+    }
+}
+
+/// Re-simulates a `Replay` in the same order `Game::update` runs: moves for the tick are
+/// applied first, then the board's timers advance.
+pub struct Playback<'a> {
+    replay: &'a Replay,
+    board: Board,
+    tick: u64,
+    next_move: usize,
+}
+
+impl<'a> Playback<'a> {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick >= self.replay.ticks
+    }
+
+    /// Runs one tick, returning what happened on the board during it.
+    pub fn step(&mut self) -> Vec<BoardEvent> {
+        while let Some(m) = self.replay.moves.get(self.next_move) {
+            if m.tick > self.tick {
+                break;
+            }
+            self.board.check_and_move_token(m.player, m.from, m.to);
+            self.next_move += 1;
+        }
+        self.board.update(self.replay.tick_seconds);
+        self.tick += 1;
+        self.board.drain_events()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+players 2
+layout mirrored 42
+mode hill:30
+timelimit 300
+drawafter 60
+suddendeath 120:20:5
+promotion
+handicap 2 3x1.5
+army 1 PRJG
+tick 0.5
+ticks 40
+move 3 0 0 3 1 3
+move 7 1 9 4 8 4
+";
+
+    #[test]
+    fn text_round_trips() {
+        let replay = Replay::parse(SAMPLE).unwrap();
+        assert_eq!(replay.to_text(), SAMPLE);
+        assert_eq!(replay.options.setup, Setup::Mirrored);
+        assert_eq!(replay.options.mode, GameMode::Hill { seconds: 30.0 });
+        assert_eq!(replay.moves.len(), 2);
+        assert_eq!(replay.options.handicaps[1].tokens, 3);
+        assert_eq!(replay.options.handicaps[0], Handicap::default());
+        assert_eq!(replay.options.armies[0].len(), 4);
+    }
+
+    #[test]
+    fn parse_rejects_seats_outside_the_match() {
+        for line in &[
+            "handicap 0 3",
+            "handicap 3 3",
+            "handicap 4000000000 3",
+            "army 0 PRJG",
+            "army 3 PRJG",
+            "army -1 PRJG",
+        ] {
+            let text = format!("players 2\n{}\n", line);
+            assert!(Replay::parse(&text).is_err(), "accepted {:?}", line);
+        }
+        assert!(Replay::parse("players 3\nhandicap 3 2\n").is_ok());
+    }
+
+    #[test]
+    fn parse_rejects_player_counts_without_colors() {
+        let max = player_constants::COLORS.len();
+        for players in &["0", "1", "-1", "2.5", "x", ""] {
+            let text = format!("players {}\n", players);
+            assert!(Replay::parse(&text).is_err(), "accepted {:?}", players);
+        }
+        let text = format!("players {}\n", max + 1);
+        assert!(Replay::parse(&text).is_err());
+        for np in 2..=max {
+            let replay = Replay::parse(&format!("players {}\n", np)).unwrap();
+            assert_eq!(replay.num_players, np as i32);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::game::Game;
+use crate::replay::DEFAULT_TICK;
+
+/// Default cap on a match's length, in simulated seconds, before it is called a draw.
+pub const DEFAULT_MAX_SECONDS: f64 = 600.0;
+
+/// How a headless match is run.
+#[derive(Clone, Copy, Debug)]
+pub struct MatchConfig {
+    /// Simulated seconds per update.
+    pub tick: f64,
+    /// Simulated seconds after which a match that is still going is a draw, whatever the
+    /// match's own time limit.
+    pub max_seconds: f64,
+    /// Sleep for each tick instead of simulating as fast as possible, for external engines
+    /// that need wall-clock time to think.
+    pub realtime: bool,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            tick: DEFAULT_TICK,
+            max_seconds: DEFAULT_MAX_SECONDS,
+            realtime: false,
+        }
+    }
+}
+
+/// How a match ended, with per-player numbers indexed by player number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    /// `None` for a draw.
+    pub winner: Option<i32>,
+    pub seconds: f64,
+    pub kills: Vec<i32>,
+}
+
+/// Plays `game` out without a window, using whatever controllers are seated in it.
+pub fn play_match(game: &mut Game, config: &MatchConfig) -> MatchResult {
+    let tick = std::time::Duration::from_secs_f64(config.tick);
+    while game.outcome().is_none() && game.elapsed() < config.max_seconds {
+        game.update(config.tick);
+        if config.realtime {
+            std::thread::sleep(tick);
+        }
+    }
+    MatchResult {
+        winner: game.winner(),
+        seconds: game.elapsed(),
+        // summaries come in player order
+        kills: game.player_summaries().iter().map(|s| s.kills).collect(),
+    }
+}
+
+/// Running totals for one bot across all its matches.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Standing {
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub kills: i32,
+}
+
+impl Standing {
+    pub fn kills_per_game(&self) -> f64 {
+        self.kills as f64 / self.games.max(1) as f64
+    }
+}
+
+/// Totals for every bot in a tournament, indexed the way the bots were listed.
+#[derive(Clone, Debug, Default)]
+pub struct Standings {
+    pub bots: Vec<Standing>,
+    pub games: u32,
+    pub seconds: f64,
+}
+
+impl Standings {
+    pub fn new(bots: usize) -> Standings {
+        Standings {
+            bots: vec![Standing::default(); bots],
+            ..Standings::default()
+        }
+    }
+
+    /// Adds a match in which player `p` was bot `seats[p]`, returning the winning bot, or
+    /// `None` for a draw.
+    pub fn record(&mut self, seats: &[usize], result: &MatchResult) -> Option<usize> {
+        self.games += 1;
+        self.seconds += result.seconds;
+        for (p, bot) in seats.iter().enumerate() {
+            let standing = &mut self.bots[*bot];
+            standing.games += 1;
+            standing.kills += result.kills.get(p).copied().unwrap_or(0);
+            match result.winner {
+                Some(w) if w == p as i32 => standing.wins += 1,
+                Some(_) => standing.losses += 1,
+                None => standing.draws += 1,
+            }
+        }
+        result.winner.map(|w| seats[w as usize])
+    }
+
+    /// Mean simulated length of the matches recorded so far.
+    pub fn average_seconds(&self) -> f64 {
+        self.seconds / self.games.max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::RandomPlayer;
+    use crate::game_objects::MatchOptions;
+
+    fn random_match(seed: u64, config: &MatchConfig) -> MatchResult {
+        let options = MatchOptions {
+            seed,
+            ..MatchOptions::default()
+        };
+        let mut game = Game::with_options(Vec::new(), options);
+        for p in 0..2 {
+            game.add_controller(Box::new(RandomPlayer::new(p, seed + p as u64)));
+        }
+        play_match(&mut game, config)
+    }
+
+    #[test]
+    fn seeded_matches_are_reproducible() {
+        let config = MatchConfig {
+            max_seconds: 60.0,
+            ..MatchConfig::default()
+        };
+        let first = random_match(7, &config);
+        assert_eq!(random_match(7, &config), first);
+        assert_eq!(first.kills.len(), 2);
+    }
+
+    #[test]
+    fn matches_stop_at_max_seconds() {
+        // no token is Ready before its first Wait and Prep are over, so nobody can win yet
+        let config = MatchConfig {
+            max_seconds: 5.0,
+            ..MatchConfig::default()
+        };
+        let result = random_match(3, &config);
+        assert_eq!(result.winner, None);
+        assert!(result.seconds >= config.max_seconds);
+        assert!(result.seconds < config.max_seconds + config.tick + 1e-9);
+    }
+
+    #[test]
+    fn standings_follow_the_seats() {
+        let mut standings = Standings::new(3);
+        let won = MatchResult {
+            winner: Some(1),
+            seconds: 30.0,
+            kills: vec![1, 4],
+        };
+        assert_eq!(standings.record(&[0, 2], &won), Some(2));
+        let drawn = MatchResult {
+            winner: None,
+            seconds: 90.0,
+            kills: vec![2, 0],
+        };
+        assert_eq!(standings.record(&[2, 0], &drawn), None);
+
+        let first = Standing {
+            games: 2,
+            wins: 0,
+            losses: 1,
+            draws: 1,
+            kills: 1,
+        };
+        let third = Standing {
+            games: 2,
+            wins: 1,
+            losses: 0,
+            draws: 1,
+            kills: 6,
+        };
+        assert_eq!(standings.bots, vec![first, Standing::default(), third]);
+        assert_eq!(standings.bots[2].kills_per_game(), 3.0);
+        assert_eq!(standings.bots[1].kills_per_game(), 0.0);
+        assert_eq!(standings.games, 2);
+        assert_eq!(standings.average_seconds(), 60.0);
+        assert_eq!(Standings::new(2).average_seconds(), 0.0);
+    }
+}